@@ -105,6 +105,130 @@ resource "aws_dynamodb_table" "blocks" {
   }
 }
 
+resource "aws_dynamodb_table" "tokens" {
+  name         = "${local.prefix}-tokens"
+  billing_mode = "PAY_PER_REQUEST"
+  hash_key     = "id" # sha256 of the token
+
+  attribute {
+    name = "id"
+    type = "S"
+  }
+
+  attribute {
+    name = "scope"
+    type = "S"
+  }
+
+  attribute {
+    name = "created_at"
+    type = "S"
+  }
+
+  global_secondary_index {
+    hash_key        = "scope"
+    range_key       = "created_at"
+    name            = "tokens_by_scope"
+    projection_type = "ALL"
+  }
+}
+
+resource "vercel_project_environment_variable" "dynamodb_tokens" {
+  project_id = data.terraform_remote_state.project.outputs.vercel_project_id
+  key        = "DYNAMODB_TOKENS"
+  value      = aws_dynamodb_table.tokens.name
+  target     = ["production", "preview"]
+}
+
+resource "aws_dynamodb_table" "sessions" {
+  name         = "${local.prefix}-sessions"
+  billing_mode = "PAY_PER_REQUEST"
+  hash_key     = "id" # sha256 of the session cookie
+
+  attribute {
+    name = "id"
+    type = "S"
+  }
+
+  ttl {
+    attribute_name = "ttl"
+    enabled        = true
+  }
+}
+
+resource "vercel_project_environment_variable" "dynamodb_sessions" {
+  project_id = data.terraform_remote_state.project.outputs.vercel_project_id
+  key        = "DYNAMODB_SESSIONS"
+  value      = aws_dynamodb_table.sessions.name
+  target     = ["production", "preview"]
+}
+
+resource "aws_dynamodb_table" "settings" {
+  name         = "${local.prefix}-settings"
+  billing_mode = "PAY_PER_REQUEST"
+  hash_key     = "id" # composite of owner/repo
+
+  attribute {
+    name = "id"
+    type = "S"
+  }
+}
+
+resource "vercel_project_environment_variable" "dynamodb_settings" {
+  project_id = data.terraform_remote_state.project.outputs.vercel_project_id
+  key        = "DYNAMODB_SETTINGS"
+  value      = aws_dynamodb_table.settings.name
+  target     = ["production", "preview"]
+}
+
+resource "aws_dynamodb_table" "environments" {
+  name         = "${local.prefix}-environments"
+  billing_mode = "PAY_PER_REQUEST"
+  hash_key     = "id" # composite of owner/repo
+  range_key    = "name"
+
+  attribute {
+    name = "id"
+    type = "S"
+  }
+
+  attribute {
+    name = "name"
+    type = "S"
+  }
+}
+
+resource "vercel_project_environment_variable" "dynamodb_environments" {
+  project_id = data.terraform_remote_state.project.outputs.vercel_project_id
+  key        = "DYNAMODB_ENVIRONMENTS"
+  value      = aws_dynamodb_table.environments.name
+  target     = ["production", "preview"]
+}
+
+resource "aws_dynamodb_table" "deployments" {
+  name         = "${local.prefix}-deployments"
+  billing_mode = "PAY_PER_REQUEST"
+  hash_key     = "id" # composite of owner/repo/environment
+  range_key    = "started_at"
+
+  attribute {
+    name = "id"
+    type = "S"
+  }
+
+  attribute {
+    name = "started_at"
+    type = "S"
+  }
+}
+
+resource "vercel_project_environment_variable" "dynamodb_deployments" {
+  project_id = data.terraform_remote_state.project.outputs.vercel_project_id
+  key        = "DYNAMODB_DEPLOYMENTS"
+  value      = aws_dynamodb_table.deployments.name
+  target     = ["production", "preview"]
+}
+
 resource "vercel_project_environment_variable" "dynamodb_blocks" {
   project_id = data.terraform_remote_state.project.outputs.vercel_project_id
   key        = "DYNAMODB_BLOCKS"
@@ -138,6 +262,30 @@ resource "vercel_project_environment_variable" "github_client_secret" {
   sensitive  = true
 }
 
+data "aws_ssm_parameter" "github_webhook_secret" {
+  name = "/${local.prefix}/github_webhook_secret"
+}
+
+resource "vercel_project_environment_variable" "github_webhook_secret" {
+  project_id = data.terraform_remote_state.project.outputs.vercel_project_id
+  key        = "GITHUB_WEBHOOK_SECRET"
+  value      = data.aws_ssm_parameter.github_webhook_secret.value
+  target     = ["production", "preview"]
+  sensitive  = true
+}
+
+data "aws_ssm_parameter" "session_secret" {
+  name = "/${local.prefix}/session_secret"
+}
+
+resource "vercel_project_environment_variable" "session_secret" {
+  project_id = data.terraform_remote_state.project.outputs.vercel_project_id
+  key        = "SESSION_SECRET"
+  value      = data.aws_ssm_parameter.session_secret.value
+  target     = ["production", "preview"]
+  sensitive  = true
+}
+
 data "aws_iam_policy_document" "workflows_dynamodb" {
   statement {
     actions = [
@@ -167,6 +315,71 @@ data "aws_iam_policy_document" "blocks_dynamodb" {
   }
 }
 
+data "aws_iam_policy_document" "tokens_dynamodb" {
+  statement {
+    actions = [
+      "dynamodb:Query",
+      "dynamodb:GetItem",
+      "dynamodb:PutItem",
+      "dynamodb:UpdateItem",
+      "dynamodb:DeleteItem",
+    ]
+    resources = [
+      aws_dynamodb_table.tokens.arn,
+      "${aws_dynamodb_table.tokens.arn}/index/*",
+    ]
+  }
+}
+
+data "aws_iam_policy_document" "sessions_dynamodb" {
+  statement {
+    actions = [
+      "dynamodb:GetItem",
+      "dynamodb:PutItem",
+      "dynamodb:DeleteItem",
+    ]
+    resources = [
+      aws_dynamodb_table.sessions.arn,
+    ]
+  }
+}
+
+data "aws_iam_policy_document" "settings_dynamodb" {
+  statement {
+    actions = [
+      "dynamodb:GetItem",
+      "dynamodb:UpdateItem",
+    ]
+    resources = [
+      aws_dynamodb_table.settings.arn,
+    ]
+  }
+}
+
+data "aws_iam_policy_document" "environments_dynamodb" {
+  statement {
+    actions = [
+      "dynamodb:Query",
+      "dynamodb:UpdateItem",
+    ]
+    resources = [
+      aws_dynamodb_table.environments.arn,
+    ]
+  }
+}
+
+data "aws_iam_policy_document" "deployments_dynamodb" {
+  statement {
+    actions = [
+      "dynamodb:PutItem",
+      "dynamodb:Query",
+    ]
+    resources = [
+      aws_dynamodb_table.deployments.arn,
+    ]
+  }
+}
+
 resource "aws_iam_policy" "workflows_dynamodb" {
   name   = "${local.prefix}-workflows-dynamodb"
   policy = data.aws_iam_policy_document.workflows_dynamodb.json
@@ -177,6 +390,31 @@ resource "aws_iam_policy" "blocks_dynamodb" {
   policy = data.aws_iam_policy_document.blocks_dynamodb.json
 }
 
+resource "aws_iam_policy" "tokens_dynamodb" {
+  name   = "${local.prefix}-tokens-dynamodb"
+  policy = data.aws_iam_policy_document.tokens_dynamodb.json
+}
+
+resource "aws_iam_policy" "sessions_dynamodb" {
+  name   = "${local.prefix}-sessions-dynamodb"
+  policy = data.aws_iam_policy_document.sessions_dynamodb.json
+}
+
+resource "aws_iam_policy" "settings_dynamodb" {
+  name   = "${local.prefix}-settings-dynamodb"
+  policy = data.aws_iam_policy_document.settings_dynamodb.json
+}
+
+resource "aws_iam_policy" "environments_dynamodb" {
+  name   = "${local.prefix}-environments-dynamodb"
+  policy = data.aws_iam_policy_document.environments_dynamodb.json
+}
+
+resource "aws_iam_policy" "deployments_dynamodb" {
+  name   = "${local.prefix}-deployments-dynamodb"
+  policy = data.aws_iam_policy_document.deployments_dynamodb.json
+}
+
 resource "aws_iam_user" "pipedream" {
   name          = "${local.prefix}-api"
   force_destroy = true
@@ -192,6 +430,31 @@ resource "aws_iam_user_policy_attachment" "blocks_dynamodb" {
   policy_arn = aws_iam_policy.blocks_dynamodb.arn
 }
 
+resource "aws_iam_user_policy_attachment" "tokens_dynamodb" {
+  user       = aws_iam_user.pipedream.name
+  policy_arn = aws_iam_policy.tokens_dynamodb.arn
+}
+
+resource "aws_iam_user_policy_attachment" "sessions_dynamodb" {
+  user       = aws_iam_user.pipedream.name
+  policy_arn = aws_iam_policy.sessions_dynamodb.arn
+}
+
+resource "aws_iam_user_policy_attachment" "settings_dynamodb" {
+  user       = aws_iam_user.pipedream.name
+  policy_arn = aws_iam_policy.settings_dynamodb.arn
+}
+
+resource "aws_iam_user_policy_attachment" "environments_dynamodb" {
+  user       = aws_iam_user.pipedream.name
+  policy_arn = aws_iam_policy.environments_dynamodb.arn
+}
+
+resource "aws_iam_user_policy_attachment" "deployments_dynamodb" {
+  user       = aws_iam_user.pipedream.name
+  policy_arn = aws_iam_policy.deployments_dynamodb.arn
+}
+
 resource "aws_iam_access_key" "pipedream" {
   user    = aws_iam_user.pipedream.name
   pgp_key = "keybase:dgls"
@@ -19,7 +19,15 @@ pub fn App() -> impl IntoView {
             <main>
                 <Routes>
                     <Route path="" view=pages::Home/>
+                    <Route path="overview" view=pages::Overview/>
                     <Route path="dashboard" view=pages::Dashboard/>
+                    <Route path="dashboard/:owner/:repo" view=pages::Dashboard/>
+                    <Route path="dashboard/:owner/:repo/:workflow" view=pages::WorkflowDetail/>
+                    <Route path="environments" view=pages::Environments/>
+                    <Route
+                        path="environments/:owner/:repo/:environment"
+                        view=pages::DeploymentHistory
+                    />
                 </Routes>
             </main>
         </Router>
@@ -37,66 +45,105 @@ pub async fn create_workflow(
     repo: String,
     owner: String,
     sha: String,
-    stability_period_minutes: usize,
-    environments: String,
+    stability_period_minutes: Option<usize>,
+    environments: Option<String>,
     commit_message: String,
+    labels: Option<String>,
 ) -> Result<Response, ServerFnError> {
-    use super::workflow;
-    use http::{HeaderMap, StatusCode};
-    use leptos::expect_context;
-    use leptos_axum::{extract, ResponseOptions};
+    use super::workflow::{self, CreateError, NewWorkflow};
+    use crate::auth::api::authenticate;
+    use crate::tokens::Operation;
+    use http::StatusCode;
+    use leptos_axum::ResponseOptions;
 
+    // An empty list leaves the pipeline to the repository's configuration.
     let environments = environments
+        .unwrap_or_default()
         .split(',')
-        .map(|s| s.to_string())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<String>>();
+    // Labels of the pull request the commit came from, comma separated, if the caller
+    // knows them.
+    let labels = labels
+        .unwrap_or_default()
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
         .collect::<Vec<String>>();
 
-    let response = expect_context::<ResponseOptions>();
-
-    let headers: HeaderMap = extract().await?;
-    let auth_header = headers.get("authorization").ok_or_else(|| {
-        response.set_status(StatusCode::UNAUTHORIZED);
-        ServerFnError::new("missing authorization header")
-    })?;
-
-    let token = auth_header.to_str().map_err(|e| {
-        log::info!(
-            "failed to get header value from header {auth_header:?}: {:#}",
-            e
-        );
-        response.set_status(StatusCode::BAD_REQUEST);
-        ServerFnError::new("invalid authorization header")
-    })?;
+    let caller = authenticate(&owner, &repo, Operation::CreateWorkflow).await?;
 
-    let token = if token.starts_with("Bearer ") {
-        token.trim_start_matches("Bearer ")
-    } else {
-        token
+    let reject = |e: CreateError| {
+        let status = match e {
+            CreateError::InvalidConfig(_) | CreateError::Invalid(_) => StatusCode::BAD_REQUEST,
+            CreateError::NotAllowed(_) => StatusCode::FORBIDDEN,
+            CreateError::AlreadyExists(_) => StatusCode::CONFLICT,
+            CreateError::Other(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        expect_context::<ResponseOptions>().set_status(status);
+        ServerFnError::new(e)
     };
-
-    crate::github::validate_oidc_token(token, &owner)
-        .await
-        .map_err(|e| {
-            response.set_status(StatusCode::UNAUTHORIZED);
-            log::info!("failed to validate token: {:#}", e);
-            ServerFnError::new("invalid authorization token")
-        })?;
-
-    workflow::client()
+    let config = workflow::fetch_config(&owner, &repo, &sha)
         .await
-        .create(workflow::CreateWorkflowRequest {
+        .map_err(reject)?;
+    let workflow = workflow::create(
+        NewWorkflow {
+            owner,
+            repo,
             git_ref,
-            repo: repo.clone(),
-            owner: owner.clone(),
-            sha: sha.clone(),
-            stability_period_minutes,
-            environments,
+            sha,
             commit_message,
-        })
-        .await
-        .map_err(ServerFnError::new)?;
+            labels,
+            environments,
+            stability_period_minutes,
+            provenance: caller.provenance(),
+            once_per_sha: false,
+        },
+        config,
+    )
+    .await
+    .map_err(reject)?;
 
     Ok(Response {
-        url: format!("https://pipedream.fly.dev/{}/{}/{}", owner, repo, sha),
+        url: workflow.url(),
     })
 }
+
+#[server(CancelWorkflow, "/api", "Url", "workflow/cancel")]
+pub async fn cancel_workflow(
+    owner: String,
+    repo: String,
+    id: i64,
+) -> Result<Response, ServerFnError> {
+    use super::workflow;
+    use crate::auth::api::authenticate;
+    use crate::tokens::Operation;
+    use http::StatusCode;
+    use leptos_axum::ResponseOptions;
+
+    authenticate(&owner, &repo, Operation::CancelWorkflow).await?;
+
+    let response = expect_context::<ResponseOptions>();
+    let created_at = chrono::DateTime::from_timestamp(id, 0).ok_or_else(|| {
+        response.set_status(StatusCode::BAD_REQUEST);
+        ServerFnError::new("invalid workflow id")
+    })?;
+    let Some(w) = workflow::client()
+        .await
+        .get(&owner, &repo, created_at)
+        .await
+        .map_err(ServerFnError::new)?
+    else {
+        response.set_status(StatusCode::NOT_FOUND);
+        return Err(ServerFnError::new("workflow not found"));
+    };
+
+    match workflow::cancel(w).await.map_err(ServerFnError::new)? {
+        Some(w) => Ok(Response { url: w.url() }),
+        None => {
+            response.set_status(StatusCode::CONFLICT);
+            Err(ServerFnError::new("workflow has already finished"))
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use crate::tokens::{self, ApiToken, Operation};
+use crate::workflow::Provenance;
+use http::{HeaderMap, StatusCode};
+use leptos::*;
+use leptos_axum::{extract, ResponseOptions};
+
+/// Whoever is calling an API endpoint. GitHub Actions present an OIDC token, while
+/// anything else (Buildkite, Jenkins, scripts) presents a pipedream API token.
+pub enum Caller {
+    GithubActions(Provenance),
+    ApiToken(ApiToken),
+}
+
+impl Caller {
+    pub fn provenance(&self) -> Option<Provenance> {
+        match self {
+            Caller::GithubActions(p) => Some(p.clone()),
+            Caller::ApiToken(_) => None,
+        }
+    }
+}
+
+/// Authenticates the caller of an API endpoint from its `authorization` header, checking
+/// that it may perform the given operation on the repository.
+pub async fn authenticate(
+    owner: &str,
+    repo: &str,
+    operation: Operation,
+) -> Result<Caller, ServerFnError> {
+    let response = expect_context::<ResponseOptions>();
+
+    let headers: HeaderMap = extract().await?;
+    let auth_header = headers.get("authorization").ok_or_else(|| {
+        response.set_status(StatusCode::UNAUTHORIZED);
+        ServerFnError::new("missing authorization header")
+    })?;
+
+    let token = auth_header.to_str().map_err(|e| {
+        log::info!(
+            "failed to get header value from header {auth_header:?}: {:#}",
+            e
+        );
+        response.set_status(StatusCode::BAD_REQUEST);
+        ServerFnError::new("invalid authorization header")
+    })?;
+
+    let token = if token.starts_with("Bearer ") {
+        token.trim_start_matches("Bearer ")
+    } else {
+        token
+    };
+
+    if token.starts_with(tokens::TOKEN_PREFIX) {
+        let client = tokens::client().await;
+        let api_token = client
+            .get(token)
+            .await
+            .map_err(|e| {
+                log::error!("failed to get api token: {:#}", e);
+                ServerFnError::new("unable to validate authorization token")
+            })?
+            .ok_or_else(|| {
+                response.set_status(StatusCode::UNAUTHORIZED);
+                ServerFnError::new("invalid authorization token")
+            })?;
+
+        if !api_token.allows(owner, repo, operation) {
+            log::info!(
+                "api token {} does not allow {:?} on {owner}/{repo}",
+                api_token.id,
+                operation
+            );
+            response.set_status(StatusCode::FORBIDDEN);
+            return Err(ServerFnError::new("authorization token not allowed"));
+        }
+
+        if let Err(e) = client.record_use(&api_token.id).await {
+            log::warn!("failed to record use of api token: {:#}", e);
+        }
+
+        return Ok(Caller::ApiToken(api_token));
+    }
+
+    let claims = crate::github::validate_oidc_token(token, owner)
+        .await
+        .map_err(|e| {
+            response.set_status(StatusCode::UNAUTHORIZED);
+            log::info!("failed to validate token: {:#}", e);
+            ServerFnError::new("invalid authorization token")
+        })?;
+
+    if !claims.allows(owner, repo, operation) {
+        log::info!(
+            "oidc token for {} does not allow {:?} on {owner}/{repo}",
+            claims.repository,
+            operation
+        );
+        response.set_status(StatusCode::FORBIDDEN);
+        return Err(ServerFnError::new("authorization token not allowed"));
+    }
+
+    Ok(Caller::GithubActions(claims.into()))
+}
@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use super::sessions::{self, SESSION_COOKIE};
+use super::{Action, Role, User};
+use crate::crypto;
+use crate::error_template::AppError;
+use crate::github;
+use crate::settings;
+use axum::{extract::Request, middleware::Next, response::Response};
+use axum_extra::extract::CookieJar;
+use chrono::{DateTime, Duration, Utc};
+use http::request::Parts;
+use leptos::*;
+
+/// The logged in user making a request, along with the GitHub user token used to act on
+/// their behalf.
+#[derive(Clone)]
+pub(crate) struct Session {
+    pub user: User,
+    pub access_token: String,
+}
+
+type Cache<T> = Mutex<HashMap<String, (DateTime<Utc>, T)>>;
+
+fn repositories() -> &'static Cache<Vec<String>> {
+    static REPOSITORIES: OnceLock<Cache<Vec<String>>> = OnceLock::new();
+    REPOSITORIES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn roles() -> &'static Cache<Role> {
+    static ROLES: OnceLock<Cache<Role>> = OnceLock::new();
+    ROLES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn org_admins() -> &'static Cache<bool> {
+    static ORG_ADMINS: OnceLock<Cache<bool>> = OnceLock::new();
+    ORG_ADMINS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn cached<T: Clone>(cache: &Cache<T>, key: &str) -> Option<T> {
+    let cache = cache.lock().unwrap();
+    cache
+        .get(key)
+        .filter(|(exp, _)| exp > &Utc::now())
+        .map(|(_, v)| v.clone())
+}
+
+fn store<T>(cache: &Cache<T>, key: String, ttl: Duration, value: T) {
+    let mut cache = cache.lock().unwrap();
+    let now = Utc::now();
+    cache.retain(|_, (exp, _)| *exp > now);
+    cache.insert(key, (now + ttl, value));
+}
+
+async fn resolve_session(secret: &str) -> Result<Option<Session>, anyhow::Error> {
+    let client = sessions::client().await;
+    let stored = match client.get(secret).await? {
+        Some(s) if !s.is_expired() => s,
+        _ => return Ok(None),
+    };
+
+    let stored = if stored.needs_refresh() {
+        client.refresh(stored).await?
+    } else {
+        stored
+    };
+
+    Ok(Some(Session {
+        user: stored.user,
+        access_token: stored.access_token,
+    }))
+}
+
+/// Resolves the session cookie to the logged in user, refreshing their GitHub token if it
+/// has expired, and makes it available to server functions through [`require_user`].
+/// Pages call server functions while rendering on the server, so this runs for page
+/// routes as well as API ones.
+pub async fn layer(jar: CookieJar, mut req: Request, next: Next) -> Response {
+    if let Some(cookie) = jar.get(SESSION_COOKIE) {
+        match resolve_session(cookie.value()).await {
+            Ok(Some(session)) => {
+                req.extensions_mut().insert(session);
+            }
+            Ok(None) => log::info!("session not found or expired"),
+            Err(e) => log::info!("failed to resolve session: {:#}", e),
+        }
+    }
+
+    next.run(req).await
+}
+
+fn reject(e: AppError) -> AppError {
+    if let Some(response) = use_context::<leptos_axum::ResponseOptions>() {
+        response.set_status(e.status_code());
+    }
+    e
+}
+
+/// Returns the logged in user, failing with [`AppError::Unauthorized`] if there isn't one.
+pub(crate) fn require_user() -> Result<Session, AppError> {
+    use_context::<Parts>()
+        .and_then(|parts| parts.extensions.get::<Session>().cloned())
+        .ok_or_else(|| reject(AppError::Unauthorized))
+}
+
+/// Lists the repositories the user can access through their installations of the
+/// pipedream GitHub app.
+pub(crate) async fn user_repositories(session: &Session) -> Result<Vec<String>, AppError> {
+    let key = crypto::sha256_hex(&session.access_token);
+    if let Some(repos) = cached(repositories(), &key) {
+        return Ok(repos);
+    }
+
+    let installations = github::list_user_installations(&session.access_token)
+        .await
+        .map_err(|e| {
+            log::error!("failed to list user installations: {:#}", e);
+            reject(AppError::InternalServerError)
+        })?;
+
+    let mut repos = vec![];
+    for i in installations {
+        let r = github::list_installation_repositories(&session.access_token, i.id)
+            .await
+            .map_err(|e| {
+                log::error!("failed to list installation repositories: {:#}", e);
+                reject(AppError::InternalServerError)
+            })?;
+        repos.extend(r);
+    }
+    store(repositories(), key, Duration::minutes(1), repos.clone());
+
+    Ok(repos)
+}
+
+/// Returns the logged in user, provided their installations grant them access to the given
+/// repository.
+pub(crate) async fn require_repo_access(owner: &str, repo: &str) -> Result<Session, AppError> {
+    let session = require_user()?;
+
+    let full_name = format!("{owner}/{repo}");
+    if !user_repositories(&session).await?.contains(&full_name) {
+        log::info!(
+            "user {} does not have access to {full_name}",
+            session.user.login
+        );
+        return Err(reject(AppError::Forbidden));
+    }
+
+    Ok(session)
+}
+
+fn github_role(permissions: github::RepositoryPermissions) -> Role {
+    if permissions.admin {
+        Role::Admin
+    } else if permissions.maintain {
+        Role::Maintain
+    } else if permissions.push {
+        Role::Write
+    } else if permissions.triage {
+        Role::Triage
+    } else {
+        Role::Read
+    }
+}
+
+/// Works out the user's role on the repository, the higher of their GitHub repository
+/// permission and any role granted to them in pipedream.
+pub(crate) async fn repository_role(
+    session: &Session,
+    owner: &str,
+    repo: &str,
+) -> Result<Role, AppError> {
+    let key = format!(
+        "{owner}/{repo}:{}",
+        crypto::sha256_hex(&session.access_token)
+    );
+    if let Some(role) = cached(roles(), &key) {
+        return Ok(role);
+    }
+
+    let permissions = github::get_repository_permissions(&session.access_token, owner, repo)
+        .await
+        .map_err(|e| {
+            log::error!("failed to get repository permissions: {:#}", e);
+            reject(AppError::InternalServerError)
+        })?;
+
+    let settings = settings::client()
+        .await
+        .get(owner, repo)
+        .await
+        .map_err(|e| {
+            log::error!("failed to get repo settings: {:#}", e);
+            reject(AppError::InternalServerError)
+        })?;
+
+    let granted = settings.role_grants.get(&session.user.login).copied();
+    let role = github_role(permissions).max(granted.unwrap_or(Role::Read));
+    store(roles(), key, Duration::minutes(1), role);
+
+    Ok(role)
+}
+
+/// Drops any cached roles for the repository, so changes to role grants apply straight
+/// away.
+pub(crate) fn forget_roles(owner: &str, repo: &str) {
+    let prefix = format!("{owner}/{repo}:");
+    roles()
+        .lock()
+        .unwrap()
+        .retain(|k, _| !k.starts_with(&prefix));
+}
+
+/// Returns the logged in user, provided they can access the repository and their role
+/// allows them to perform the action.
+pub(crate) async fn require_action(
+    owner: &str,
+    repo: &str,
+    action: Action,
+) -> Result<Session, AppError> {
+    let session = require_repo_access(owner, repo).await?;
+
+    let role = repository_role(&session, owner, repo).await?;
+    if !role.allows(action) {
+        log::info!(
+            "user {} with role {role} on {owner}/{repo} cannot {action:?}",
+            session.user.login
+        );
+        return Err(reject(AppError::Forbidden));
+    }
+
+    Ok(session)
+}
+
+/// Whether the user administers everything the owner has, so may manage what applies to
+/// all of its repositories: they are an admin of the organisation, or the owner is them.
+pub(crate) async fn is_owner_admin(session: &Session, owner: &str) -> Result<bool, AppError> {
+    if session.user.login.eq_ignore_ascii_case(owner) {
+        return Ok(true);
+    }
+
+    let key = format!("{owner}:{}", crypto::sha256_hex(&session.access_token));
+    if let Some(admin) = cached(org_admins(), &key) {
+        return Ok(admin);
+    }
+
+    let admin = github::is_org_admin(&session.access_token, owner, &session.user.login)
+        .await
+        .map_err(|e| {
+            log::error!("failed to get organisation membership: {:#}", e);
+            reject(AppError::InternalServerError)
+        })?;
+    store(org_admins(), key, Duration::minutes(1), admin);
+
+    Ok(admin)
+}
+
+/// Returns the logged in user, provided they administer everything the owner has.
+pub(crate) async fn require_owner_admin(owner: &str) -> Result<Session, AppError> {
+    let session = require_user()?;
+
+    if !is_owner_admin(&session, owner).await? {
+        log::info!("user {} is not an admin of {owner}", session.user.login);
+        return Err(reject(AppError::Forbidden));
+    }
+
+    Ok(session)
+}
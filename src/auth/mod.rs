@@ -0,0 +1,175 @@
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+pub(crate) mod api;
+#[cfg(feature = "ssr")]
+mod middleware;
+mod permissions;
+#[cfg(feature = "ssr")]
+mod sessions;
+#[cfg(feature = "ssr")]
+mod state;
+
+#[cfg(feature = "ssr")]
+pub use middleware::layer;
+#[cfg(feature = "ssr")]
+pub(crate) use middleware::{
+    forget_roles, is_owner_admin, repository_role, require_action, require_owner_admin,
+    require_repo_access, require_user, user_repositories, Session,
+};
+pub use permissions::{Action, Role};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct User {
+    pub id: i64,
+    pub login: String,
+}
+
+#[server(Logout)]
+pub async fn logout() -> Result<(), ServerFnError> {
+    use axum_extra::extract::CookieJar;
+    use http::header;
+    use leptos::expect_context;
+    use leptos_axum::extract;
+    use sessions::SESSION_COOKIE;
+    use time::Duration;
+
+    let response = expect_context::<leptos_axum::ResponseOptions>();
+    let jar: CookieJar = extract().await?;
+
+    if let Some(cookie) = jar.get(SESSION_COOKIE) {
+        sessions::client()
+            .await
+            .delete(cookie.value())
+            .await
+            .map_err(|e| {
+                log::error!("failed to delete session: {:#}", e);
+                ServerFnError::new("unable to log out")
+            })?;
+    }
+
+    let cookie = sessions::cookie(SESSION_COOKIE, String::new(), Duration::ZERO);
+    if let Ok(cookie) = header::HeaderValue::from_str(&cookie.to_string()) {
+        response.append_header(header::SET_COOKIE, cookie);
+    }
+
+    leptos_axum::redirect("/");
+
+    Ok(())
+}
+
+/// Starts a GitHub login, redirecting to GitHub with a `state` bound to a short-lived
+/// pre-login cookie. After logging in the user is sent back to `return_to`.
+#[server(Login, "/api", "GetJson", "github/login")]
+pub async fn login(return_to: Option<String>) -> Result<(), ServerFnError> {
+    use axum_extra::extract::cookie::SameSite;
+    use http::header;
+    use leptos_axum::ResponseOptions;
+    use state::{STATE_COOKIE, STATE_TTL_MINUTES};
+    use time::Duration;
+
+    let response = expect_context::<ResponseOptions>();
+
+    let (nonce, state) = state::sign(return_to.as_deref()).map_err(|e| {
+        log::error!("failed to sign login state: {:#}", e);
+        ServerFnError::new("unable to log in")
+    })?;
+
+    // The cookie has to survive the redirect back from GitHub, so can't be strict.
+    let mut cookie = sessions::cookie(STATE_COOKIE, nonce, Duration::minutes(STATE_TTL_MINUTES));
+    cookie.set_same_site(SameSite::Lax);
+    if let Ok(cookie) = header::HeaderValue::from_str(&cookie.to_string()) {
+        response.append_header(header::SET_COOKIE, cookie);
+    }
+
+    let mut url = reqwest::Url::parse("https://github.com/login/oauth/authorize")?;
+    url.query_pairs_mut()
+        .append_pair("client_id", &std::env::var("GITHUB_CLIENT_ID")?)
+        .append_pair(
+            "redirect_uri",
+            &format!("{}/api/github/callback", crate::base_url()),
+        )
+        .append_pair("state", &state);
+
+    leptos_axum::redirect(url.as_str());
+
+    Ok(())
+}
+
+#[server(Authorize, "/api", "GetJson", "github/callback")]
+pub async fn authorize(code: String, state: String) -> Result<(), ServerFnError> {
+    use crate::github;
+    use axum_extra::extract::CookieJar;
+    use http::header;
+    use leptos::expect_context;
+    use leptos_axum::{extract, ResponseOptions};
+    use sessions::SESSION_COOKIE;
+    use state::STATE_COOKIE;
+    use time::Duration;
+
+    let response = expect_context::<ResponseOptions>();
+    let jar: CookieJar = extract().await?;
+
+    let nonce = jar.get(STATE_COOKIE).map(|c| c.value().to_string());
+    let return_to = match nonce.map(|nonce| state::verify(&state, &nonce)) {
+        Some(Ok(return_to)) => return_to,
+        Some(Err(e)) => {
+            log::info!("rejecting login with invalid state: {:#}", e);
+            leptos_axum::redirect("/");
+            return Err(ServerFnError::new("invalid login state"));
+        }
+        None => {
+            log::info!("rejecting login without a login cookie");
+            leptos_axum::redirect("/");
+            return Err(ServerFnError::new("invalid login state"));
+        }
+    };
+
+    let cookie = sessions::cookie(STATE_COOKIE, String::new(), Duration::ZERO);
+    if let Ok(cookie) = header::HeaderValue::from_str(&cookie.to_string()) {
+        response.append_header(header::SET_COOKIE, cookie);
+    }
+
+    let auth_tokens = match github::exchange_oauth_token(&code).await {
+        Err(e) => {
+            log::error!("failed to exchange oauth token: {:#}", e);
+            leptos_axum::redirect("/");
+            Err(ServerFnError::new("unable to list workflows"))
+        }
+        Ok(v) => Ok(v),
+    }?;
+
+    let user = github::get_user(&auth_tokens.access_token)
+        .await
+        .map_err(|e| {
+            log::error!("failed to get github user: {:#}", e);
+            ServerFnError::new("unable to log in")
+        })?;
+
+    let (secret, session) = sessions::client()
+        .await
+        .create(
+            User {
+                id: user.id,
+                login: user.login,
+            },
+            auth_tokens,
+        )
+        .await
+        .map_err(|e| {
+            log::error!("failed to create session: {:#}", e);
+            ServerFnError::new("unable to log in")
+        })?;
+
+    let max_age = (session.expires_at - chrono::Utc::now()).num_seconds();
+    let cookie = sessions::cookie(SESSION_COOKIE, secret, Duration::seconds(max_age));
+
+    if let Ok(cookie) = header::HeaderValue::from_str(&cookie.to_string()) {
+        response.append_header(header::SET_COOKIE, cookie);
+    }
+
+    leptos_axum::redirect(&return_to);
+
+    Ok(())
+}
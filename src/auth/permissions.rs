@@ -0,0 +1,101 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// A user's role on a repository, mirroring GitHub's repository permission levels.
+#[derive(Debug, Clone, Serialize, Deserialize, Copy, PartialEq, Eq, Ord, PartialOrd, Hash)]
+pub enum Role {
+    Read,
+    Triage,
+    Write,
+    Maintain,
+    Admin,
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Role::Read => "Read",
+            Role::Triage => "Triage",
+            Role::Write => "Write",
+            Role::Maintain => "Maintain",
+            Role::Admin => "Admin",
+        })
+    }
+}
+
+/// Something a user can do to a repository's deployments through pipedream.
+#[derive(Debug, Clone, Serialize, Deserialize, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Pause,
+    Approve,
+    Cancel,
+    Redeploy,
+    Promote,
+    ManageTokens,
+    ManageRoles,
+    ManageSettings,
+}
+
+impl Action {
+    pub fn all() -> Vec<Action> {
+        vec![
+            Action::Pause,
+            Action::Approve,
+            Action::Cancel,
+            Action::Redeploy,
+            Action::Promote,
+            Action::ManageTokens,
+            Action::ManageRoles,
+            Action::ManageSettings,
+        ]
+    }
+
+    /// The minimum role a user needs to perform the action.
+    pub fn required_role(&self) -> Role {
+        match self {
+            Action::Cancel | Action::Redeploy => Role::Write,
+            Action::Pause | Action::Approve | Action::Promote => Role::Maintain,
+            Action::ManageTokens | Action::ManageRoles | Action::ManageSettings => Role::Admin,
+        }
+    }
+}
+
+impl Role {
+    pub fn all() -> Vec<Role> {
+        vec![
+            Role::Read,
+            Role::Triage,
+            Role::Write,
+            Role::Maintain,
+            Role::Admin,
+        ]
+    }
+
+    pub fn allows(&self, action: Action) -> bool {
+        *self >= action.required_role()
+    }
+
+    pub fn allowed_actions(&self) -> Vec<Action> {
+        Action::all()
+            .into_iter()
+            .filter(|a| self.allows(*a))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_allows() {
+        assert!(!Role::Read.allows(Action::Cancel));
+        assert!(!Role::Triage.allows(Action::Redeploy));
+        assert!(Role::Write.allows(Action::Cancel));
+        assert!(!Role::Write.allows(Action::Approve));
+        assert!(Role::Maintain.allows(Action::Pause));
+        assert!(!Role::Maintain.allows(Action::ManageTokens));
+        assert_eq!(Role::Admin.allowed_actions(), Action::all());
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use super::User;
+use crate::aws::{config, to_attribute_value, DynamodbClient, DynamodbError};
+use crate::crypto;
+use crate::github;
+use anyhow::Context;
+use axum_extra::extract::cookie::{Cookie, SameSite};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::OnceCell;
+
+/// The cookie holding the opaque session id.
+pub(crate) const SESSION_COOKIE: &str = "session";
+
+/// A login session. The cookie only carries an opaque id, with the GitHub user tokens
+/// kept here. Sessions are keyed on the SHA-256 of the id so the table alone can't be
+/// used to hijack them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct StoredSession {
+    pub id: String,
+    pub user: User,
+    pub access_token: String,
+    pub access_token_expires_at: DateTime<Utc>,
+    pub refresh_token: String,
+    pub expires_at: DateTime<Utc>,
+    /// Unix timestamp after which DynamoDB deletes the session.
+    pub ttl: i64,
+}
+
+impl StoredSession {
+    fn new(id: String, user: User, tokens: github::OauthTokenResponse) -> Self {
+        let now = Utc::now();
+        // Refresh a little early, so the token doesn't expire mid-request.
+        let access_token_expires_at = now + Duration::seconds(tokens.expires_in - 30);
+        let expires_at = now
+            + tokens
+                .refresh_token_expires_in
+                .map(Duration::seconds)
+                .unwrap_or_else(|| Duration::days(10));
+
+        StoredSession {
+            id,
+            user,
+            access_token: tokens.access_token,
+            access_token_expires_at,
+            refresh_token: tokens.refresh_token,
+            expires_at,
+            ttl: expires_at.timestamp(),
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at < Utc::now()
+    }
+
+    pub fn needs_refresh(&self) -> bool {
+        self.access_token_expires_at < Utc::now()
+    }
+}
+
+pub(crate) struct Client {
+    table: DynamodbClient,
+}
+
+impl Client {
+    async fn new() -> Client {
+        let table_name =
+            std::env::var("DYNAMODB_SESSIONS").expect("DYNAMODB_SESSIONS is required but not set");
+
+        Client {
+            table: DynamodbClient::new(config().await, table_name),
+        }
+    }
+
+    /// Starts a session for the user, returning the id to hand out in the session cookie.
+    pub async fn create(
+        &self,
+        user: User,
+        tokens: github::OauthTokenResponse,
+    ) -> Result<(String, StoredSession), anyhow::Error> {
+        let secret = crypto::random_hex(32)?;
+        let session = StoredSession::new(crypto::sha256_hex(&secret), user, tokens);
+
+        self.table
+            .put_item(session.clone())
+            .await
+            .context("create session")?;
+
+        Ok((secret, session))
+    }
+
+    async fn get_by_id(&self, id: &str) -> Result<Option<StoredSession>, anyhow::Error> {
+        let mut key = HashMap::new();
+        key.insert("id".to_string(), id.to_string());
+
+        match self.table.get_item(key).await {
+            Ok(session) => Ok(Some(session)),
+            Err(DynamodbError::NotFound()) => Ok(None),
+            Err(e) => Err(e).context("get session"),
+        }
+    }
+
+    pub async fn get(&self, secret: &str) -> Result<Option<StoredSession>, anyhow::Error> {
+        self.get_by_id(&crypto::sha256_hex(secret)).await
+    }
+
+    /// Exchanges the refresh token of the session for a new access token.
+    pub async fn refresh(&self, session: StoredSession) -> Result<StoredSession, anyhow::Error> {
+        let tokens = match github::refresh_oauth_token(&session.refresh_token).await {
+            Ok(tokens) => tokens,
+            Err(e) => {
+                // Refresh tokens are single use, so a concurrent request may have already
+                // refreshed the session, in which case use what it stored.
+                if let Some(current) = self.get_by_id(&session.id).await? {
+                    if current.refresh_token != session.refresh_token && !current.needs_refresh() {
+                        return Ok(current);
+                    }
+                }
+                return Err(e).context("refreshing oauth token");
+            }
+        };
+
+        let refreshed = StoredSession::new(session.id, session.user, tokens);
+        self.table
+            .put_item(refreshed.clone())
+            .await
+            .context("storing refreshed session")?;
+
+        Ok(refreshed)
+    }
+
+    pub async fn delete(&self, secret: &str) -> Result<(), anyhow::Error> {
+        self.table
+            .run_delete(
+                self.table
+                    .delete()
+                    .key("id", to_attribute_value(crypto::sha256_hex(secret))?),
+            )
+            .await
+            .context("delete session")
+    }
+}
+
+pub(crate) async fn client() -> &'static Client {
+    static CONFIG: OnceCell<Client> = OnceCell::const_new();
+    CONFIG.get_or_init(Client::new).await
+}
+
+/// Builds a cookie valid for the whole site. When running locally on `127.0.0.1` no
+/// `DOMAIN` is set, in which case we leave off the domain and `Secure` attributes, as
+/// neither work over plain http on an IP address.
+pub(crate) fn cookie(
+    name: &'static str,
+    value: String,
+    max_age: time::Duration,
+) -> Cookie<'static> {
+    let cookie = Cookie::build((name, value))
+        .path("/")
+        .same_site(SameSite::Strict)
+        .max_age(max_age)
+        .http_only(true);
+
+    match std::env::var("DOMAIN") {
+        Ok(domain) => cookie.domain(domain).secure(true).build(),
+        Err(_) => cookie.build(),
+    }
+}
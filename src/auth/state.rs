@@ -0,0 +1,134 @@
+use crate::crypto;
+use anyhow::Context;
+use chrono::{DateTime, Duration, Utc};
+
+/// The pre-login cookie, holding the nonce the OAuth `state` parameter is bound to.
+pub(crate) const STATE_COOKIE: &str = "oauth_state";
+
+/// How long a user has to complete the GitHub login.
+pub(crate) const STATE_TTL_MINUTES: i64 = 10;
+
+const DEFAULT_RETURN_TO: &str = "/dashboard";
+
+fn secret() -> Result<Vec<u8>, anyhow::Error> {
+    std::env::var("SESSION_SECRET")
+        .map(|s| s.into_bytes())
+        .context("SESSION_SECRET is required but not set")
+}
+
+/// Only allow returning to paths on this site, to avoid becoming an open redirect.
+fn sanitize_return_to(return_to: Option<&str>) -> String {
+    match return_to {
+        Some(r) if r.starts_with('/') && !r.starts_with("//") && !r.contains('\\') => r.to_string(),
+        _ => DEFAULT_RETURN_TO.to_string(),
+    }
+}
+
+fn sign_with(
+    key: &[u8],
+    nonce: &str,
+    return_to: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<String, anyhow::Error> {
+    let payload = format!(
+        "{nonce}.{}.{}",
+        expires_at.timestamp(),
+        crypto::to_hex(return_to.as_bytes())
+    );
+    let signature = crypto::hmac_sha256_hex(key, payload.as_bytes())?;
+    Ok(format!("{payload}.{signature}"))
+}
+
+fn verify_with(
+    key: &[u8],
+    state: &str,
+    nonce: &str,
+    now: DateTime<Utc>,
+) -> Result<String, anyhow::Error> {
+    let parts = state.split('.').collect::<Vec<_>>();
+    let [state_nonce, expires_at, return_to, signature] = parts[..] else {
+        anyhow::bail!("malformed state");
+    };
+
+    let payload = format!("{state_nonce}.{expires_at}.{return_to}");
+    let expected = crypto::hmac_sha256_hex(key, payload.as_bytes())?;
+    if !crypto::constant_time_eq(&expected, signature) {
+        anyhow::bail!("invalid state signature");
+    }
+
+    if !crypto::constant_time_eq(state_nonce, nonce) {
+        anyhow::bail!("state does not match login cookie");
+    }
+
+    let expires_at = expires_at.parse::<i64>().context("parsing state expiry")?;
+    if expires_at < now.timestamp() {
+        anyhow::bail!("state has expired");
+    }
+
+    let return_to = crypto::from_hex(return_to)
+        .and_then(|r| String::from_utf8(r).ok())
+        .context("decoding return_to")?;
+
+    Ok(sanitize_return_to(Some(&return_to)))
+}
+
+/// Creates the `state` parameter for a GitHub login, returning it alongside the nonce to
+/// store in the pre-login cookie.
+pub(crate) fn sign(return_to: Option<&str>) -> Result<(String, String), anyhow::Error> {
+    let nonce = crypto::random_hex(16)?;
+    let state = sign_with(
+        &secret()?,
+        &nonce,
+        &sanitize_return_to(return_to),
+        Utc::now() + Duration::minutes(STATE_TTL_MINUTES),
+    )?;
+    Ok((nonce, state))
+}
+
+/// Verifies the `state` GitHub handed back matches the nonce in the pre-login cookie,
+/// returning where the user should be sent after logging in.
+pub(crate) fn verify(state: &str, nonce: &str) -> Result<String, anyhow::Error> {
+    verify_with(&secret()?, state, nonce, Utc::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &[u8] = b"test-secret";
+
+    #[test]
+    fn test_state_round_trip() {
+        let expires_at = Utc::now() + Duration::minutes(10);
+        let state = sign_with(KEY, "abc123", "/dashboard/o/r", expires_at).unwrap();
+
+        let return_to = verify_with(KEY, &state, "abc123", Utc::now()).unwrap();
+        assert_eq!(return_to, "/dashboard/o/r");
+    }
+
+    #[test]
+    fn test_state_rejected() {
+        let expires_at = Utc::now() + Duration::minutes(10);
+        let state = sign_with(KEY, "abc123", "/dashboard", expires_at).unwrap();
+
+        // bound to a different login cookie
+        assert!(verify_with(KEY, &state, "def456", Utc::now()).is_err());
+        // signed with a different key
+        assert!(verify_with(b"other", &state, "abc123", Utc::now()).is_err());
+        // expired
+        let later = Utc::now() + Duration::minutes(11);
+        assert!(verify_with(KEY, &state, "abc123", later).is_err());
+        // tampered with
+        let tampered = state.replacen("abc123", "abc124", 1);
+        assert!(verify_with(KEY, &tampered, "abc124", Utc::now()).is_err());
+    }
+
+    #[test]
+    fn test_return_to_is_local() {
+        assert_eq!(sanitize_return_to(Some("/dashboard/o/r")), "/dashboard/o/r");
+        assert_eq!(sanitize_return_to(Some("//evil.com")), "/dashboard");
+        assert_eq!(sanitize_return_to(Some("https://evil.com")), "/dashboard");
+        assert_eq!(sanitize_return_to(Some("/\\evil.com")), "/dashboard");
+        assert_eq!(sanitize_return_to(None), "/dashboard");
+    }
+}
@@ -2,14 +2,16 @@
 use std::collections::HashMap;
 
 use anyhow::{Context, Error};
+use aws_sdk_dynamodb::operation::delete_item::builders::DeleteItemFluentBuilder;
 use aws_sdk_dynamodb::operation::query::builders::QueryFluentBuilder;
+use aws_sdk_dynamodb::operation::transact_write_items::TransactWriteItemsError;
 use aws_sdk_dynamodb::operation::update_item::builders::UpdateItemFluentBuilder;
-use aws_sdk_dynamodb::types::ReturnValue;
+use aws_sdk_dynamodb::types::{Put, ReturnValue, Select, TransactWriteItem};
 use aws_sdk_dynamodb::Client;
 use aws_types::sdk_config;
 use serde::{Deserialize, Serialize};
-use serde_dynamo::{from_item, from_items, to_item};
 pub use serde_dynamo::to_attribute_value;
+use serde_dynamo::{from_item, from_items, to_item};
 
 #[derive(Clone)]
 pub struct DynamodbClient {
@@ -21,6 +23,9 @@ pub struct DynamodbClient {
 pub enum DynamodbError {
     #[error("The requested item could not be found")]
     NotFound(),
+    /// The condition on the item at this index of a transaction didn't hold.
+    #[error("The condition on item {0} of the transaction failed")]
+    ConditionFailed(usize),
     #[error("An unexpected error occurred: {0:#}")]
     Unexpected(Error),
 }
@@ -53,6 +58,53 @@ impl DynamodbClient {
         Ok(())
     }
 
+    /// A put for `transact_write`, which cancels the transaction unless `condition` holds.
+    pub fn transact_put<T: Serialize>(
+        &self,
+        item: T,
+        condition: Option<&str>,
+    ) -> Result<TransactWriteItem, DynamodbError> {
+        let db_item = to_item(item).with_context(|| "failed to convert item to dynamodb item")?;
+        let put = Put::builder()
+            .table_name(&self.table_name)
+            .set_item(Some(db_item))
+            .set_condition_expression(condition.map(str::to_string))
+            .build()
+            .with_context(|| "failed to build put")?;
+
+        Ok(TransactWriteItem::builder().put(put).build())
+    }
+
+    /// Writes the items together, or none of them if the condition on any fails.
+    pub async fn transact_write(&self, items: Vec<TransactWriteItem>) -> Result<(), DynamodbError> {
+        let res = self
+            .client
+            .transact_write_items()
+            .set_transact_items(Some(items))
+            .send()
+            .await;
+
+        match res {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                if let Some(TransactWriteItemsError::TransactionCanceledException(c)) =
+                    e.as_service_error()
+                {
+                    let failed = c
+                        .cancellation_reasons()
+                        .iter()
+                        .position(|r| r.code() == Some("ConditionalCheckFailed"));
+                    if let Some(idx) = failed {
+                        return Err(DynamodbError::ConditionFailed(idx));
+                    }
+                }
+                Err(anyhow::Error::new(e)
+                    .context("failed to write items")
+                    .into())
+            }
+        }
+    }
+
     pub async fn get_item<'a, T: Deserialize<'a>>(
         &self,
         key: HashMap<String, impl Serialize>,
@@ -83,18 +135,104 @@ impl DynamodbClient {
         self.client.query().table_name(&self.table_name)
     }
 
+    /// Runs the query, following pagination to return every matching item.
     pub async fn run_query<'a, T: Deserialize<'a>>(
         &self,
         query: QueryFluentBuilder,
     ) -> Result<Vec<T>, DynamodbError> {
-        let res = query.send().await.with_context(|| "failed to query")?;
-
-        match res.items {
-            None => Ok(vec![]),
-            Some(items) => {
-                let items: Vec<T> =
-                    from_items(items).with_context(|| "failed to deserialize results")?;
-                Ok(items)
+        let mut items = vec![];
+        let mut start = None;
+        loop {
+            let res = query
+                .clone()
+                .set_exclusive_start_key(start)
+                .send()
+                .await
+                .with_context(|| "failed to query")?;
+
+            if let Some(page) = res.items {
+                let page: Vec<T> =
+                    from_items(page).with_context(|| "failed to deserialize results")?;
+                items.extend(page);
+            }
+
+            match res.last_evaluated_key {
+                Some(key) => start = Some(key),
+                None => return Ok(items),
+            }
+        }
+    }
+
+    /// Runs the query from `cursor`, returning at least `limit` items where there are
+    /// that many, along with a cursor to fetch the next page from. Cursors are opaque,
+    /// and only work for tables and indexes whose keys are all strings.
+    pub async fn run_query_page<'a, T: Deserialize<'a>>(
+        &self,
+        query: QueryFluentBuilder,
+        cursor: Option<String>,
+        limit: usize,
+    ) -> Result<(Vec<T>, Option<String>), DynamodbError> {
+        let mut start = match cursor {
+            Some(cursor) => {
+                let key: HashMap<String, String> =
+                    serde_json::from_str(&cursor).with_context(|| "failed to parse cursor")?;
+                Some(to_item(key).with_context(|| "failed to convert cursor to key")?)
+            }
+            None => None,
+        };
+
+        // Filters apply after the limit, so keep going until we have enough items.
+        let mut items = vec![];
+        loop {
+            let res = query
+                .clone()
+                .limit(limit as i32)
+                .set_exclusive_start_key(start)
+                .send()
+                .await
+                .with_context(|| "failed to query")?;
+
+            if let Some(page) = res.items {
+                let page: Vec<T> =
+                    from_items(page).with_context(|| "failed to deserialize results")?;
+                items.extend(page);
+            }
+
+            start = res.last_evaluated_key;
+            if start.is_none() || items.len() >= limit {
+                break;
+            }
+        }
+
+        let cursor = match start {
+            Some(key) => {
+                let key: HashMap<String, String> =
+                    from_item(key).with_context(|| "failed to convert key to cursor")?;
+                Some(serde_json::to_string(&key).with_context(|| "failed to serialize cursor")?)
+            }
+            None => None,
+        };
+
+        Ok((items, cursor))
+    }
+
+    /// Counts the items matching the query, following pagination.
+    pub async fn run_count(&self, query: QueryFluentBuilder) -> Result<usize, DynamodbError> {
+        let mut count = 0;
+        let mut start = None;
+        loop {
+            let res = query
+                .clone()
+                .select(Select::Count)
+                .set_exclusive_start_key(start)
+                .send()
+                .await
+                .with_context(|| "failed to count")?;
+
+            count += res.count as usize;
+            match res.last_evaluated_key {
+                Some(key) => start = Some(key),
+                None => return Ok(count),
             }
         }
     }
@@ -110,7 +248,17 @@ impl DynamodbClient {
         &self,
         update: UpdateItemFluentBuilder,
     ) -> Result<T, DynamodbError> {
-        let res = update.send().await.with_context(|| "failed to update")?;
+        let res = match update.send().await {
+            Ok(res) => res,
+            Err(e)
+                if e.as_service_error()
+                    .map(|e| e.is_conditional_check_failed_exception())
+                    .unwrap_or(false) =>
+            {
+                return Err(DynamodbError::NotFound());
+            }
+            Err(e) => return Err(anyhow::Error::new(e).context("failed to update").into()),
+        };
 
         match res.attributes {
             None => Err(DynamodbError::NotFound()),
@@ -120,4 +268,24 @@ impl DynamodbClient {
             }
         }
     }
+
+    pub fn delete(&self) -> DeleteItemFluentBuilder {
+        self.client.delete_item().table_name(&self.table_name)
+    }
+
+    pub async fn run_delete(&self, delete: DeleteItemFluentBuilder) -> Result<(), DynamodbError> {
+        let res = delete.send().await;
+
+        match res {
+            Ok(_) => Ok(()),
+            Err(e)
+                if e.as_service_error()
+                    .map(|e| e.is_conditional_check_failed_exception())
+                    .unwrap_or(false) =>
+            {
+                Err(DynamodbError::NotFound())
+            }
+            Err(e) => Err(anyhow::Error::new(e).context("failed to delete").into()),
+        }
+    }
 }
@@ -127,6 +127,37 @@ fn method_to_string(m: leptos_router::Method) -> String {
     }
 }
 
+/// Converts a route path with `:param` segments into the regex vercel matches requests
+/// against, e.g. `/dashboard/:owner/:repo` to `/dashboard/([^/]+)/([^/]+)`.
+fn route_src(path: &str) -> String {
+    path.split('/')
+        .map(|segment| {
+            if segment.starts_with(':') {
+                "([^/]+)"
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// The function a route is served by. Params are written as `[param]`, since `:` can't be
+/// used in the function's directory name.
+fn route_dest(path: &str) -> String {
+    if path == "/" {
+        return "index".to_string();
+    }
+
+    path.split('/')
+        .map(|segment| match segment.strip_prefix(':') {
+            Some(param) => format!("[{param}]"),
+            None => segment.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
 #[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 struct ServerlessFunctionConfig {
@@ -182,14 +213,9 @@ fn main() {
         .map(|route| {
             let path = route.path().to_string();
             let methods = route.methods().map(method_to_string).collect::<Vec<_>>();
-            let dest = if path == "/" {
-                Some("index".to_string())
-            } else {
-                Some(path.to_string())
-            };
             Route::Source {
-                src: path.clone(),
-                dest,
+                src: route_src(&path),
+                dest: Some(route_dest(&path)),
                 methods: Some(methods),
                 headers: None,
                 r#continue: None,
@@ -206,6 +232,21 @@ fn main() {
         .collect::<Vec<_>>();
 
     routes.append(&mut ssr_routes);
+    routes.push(Route::Source {
+        src: pipedream::webhooks::WEBHOOK_PATH.to_string(),
+        dest: Some(pipedream::webhooks::WEBHOOK_PATH.to_string()),
+        methods: Some(vec!["post".to_string()]),
+        headers: None,
+        r#continue: None,
+        case_sensitive: None,
+        check: None,
+        status: None,
+        has: None,
+        missing: None,
+        locale: None,
+        middleware_raw_src: None,
+        middleware_path: None,
+    });
     routes.push(Route::Handler {
         handle: HandleValue::Filesystem,
         src: None,
@@ -252,3 +293,22 @@ fn main() {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route_params() {
+        assert_eq!(route_src("/dashboard"), "/dashboard");
+        assert_eq!(
+            route_src("/dashboard/:owner/:repo"),
+            "/dashboard/([^/]+)/([^/]+)"
+        );
+        assert_eq!(route_dest("/"), "index");
+        assert_eq!(
+            route_dest("/dashboard/:owner/:repo/:workflow"),
+            "/dashboard/[owner]/[repo]/[workflow]"
+        );
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+
+use super::Block;
+use crate::aws::{config, to_attribute_value, DynamodbClient, DynamodbError};
+use anyhow::Context;
+use chrono::Utc;
+use tokio::sync::OnceCell;
+
+pub struct Client {
+    table: DynamodbClient,
+}
+
+impl Client {
+    async fn new() -> Client {
+        let table_name =
+            std::env::var("DYNAMODB_BLOCKS").expect("DYNAMODB_BLOCKS is required but not set");
+
+        Client {
+            table: DynamodbClient::new(config().await, table_name),
+        }
+    }
+
+    pub async fn get(&self, owner: &str, repo: &str) -> Result<Option<Block>, anyhow::Error> {
+        let mut key = HashMap::new();
+        key.insert("id".to_string(), format!("{owner}/{repo}"));
+
+        match self.table.get_item(key).await {
+            Ok(block) => Ok(Some(block)),
+            Err(DynamodbError::NotFound()) => Ok(None),
+            Err(e) => Err(e).context("get block"),
+        }
+    }
+
+    pub async fn create(
+        &self,
+        owner: &str,
+        repo: &str,
+        reason: String,
+        created_by: String,
+    ) -> Result<Block, anyhow::Error> {
+        let block = Block {
+            id: format!("{owner}/{repo}"),
+            reason,
+            created_by,
+            created_at: Utc::now(),
+        };
+
+        self.table
+            .put_item(block.clone())
+            .await
+            .context("create block")?;
+
+        Ok(block)
+    }
+
+    pub async fn delete(&self, owner: &str, repo: &str) -> Result<(), anyhow::Error> {
+        self.table
+            .run_delete(
+                self.table
+                    .delete()
+                    .key("id", to_attribute_value(format!("{owner}/{repo}"))?),
+            )
+            .await
+            .context("delete block")
+    }
+}
+
+pub async fn client() -> &'static Client {
+    static CONFIG: OnceCell<Client> = OnceCell::const_new();
+    CONFIG.get_or_init(Client::new).await
+}
@@ -0,0 +1,19 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod client;
+
+#[cfg(feature = "ssr")]
+pub use client::*;
+
+/// Stops deployments to a repository until it is removed. Workflows are still created
+/// while a repository is blocked, but none of their environments progress.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Block {
+    /// Composite of owner/repo.
+    pub id: String,
+    pub reason: String,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
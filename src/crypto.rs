@@ -0,0 +1,50 @@
+use anyhow::Context;
+use openssl::hash::MessageDigest;
+use openssl::memcmp;
+use openssl::pkey::PKey;
+use openssl::rand::rand_bytes;
+use openssl::sha::sha256;
+use openssl::sign::Signer;
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Generates `len` cryptographically secure random bytes, hex encoded.
+pub fn random_hex(len: usize) -> Result<String, anyhow::Error> {
+    let mut buf = vec![0; len];
+    rand_bytes(&mut buf).context("generating random bytes")?;
+    Ok(to_hex(&buf))
+}
+
+pub fn sha256_hex(value: &str) -> String {
+    to_hex(&sha256(value.as_bytes()))
+}
+
+pub fn from_hex(value: &str) -> Option<Vec<u8>> {
+    if value.len() % 2 != 0 {
+        return None;
+    }
+
+    (0..value.len())
+        .step_by(2)
+        .map(|i| {
+            value
+                .get(i..i + 2)
+                .and_then(|b| u8::from_str_radix(b, 16).ok())
+        })
+        .collect()
+}
+
+pub fn hmac_sha256_hex(key: &[u8], value: &[u8]) -> Result<String, anyhow::Error> {
+    let key = PKey::hmac(key).context("creating hmac key")?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &key).context("creating signer")?;
+    signer.update(value).context("signing value")?;
+    let signature = signer.sign_to_vec().context("signing value")?;
+    Ok(to_hex(&signature))
+}
+
+/// Compares two strings in constant time, to avoid leaking how much of a signature matched.
+pub fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len() && memcmp::eq(a.as_bytes(), b.as_bytes())
+}
@@ -0,0 +1,106 @@
+use super::LiveEnvironment;
+use crate::aws::{config, to_attribute_value, DynamodbClient, DynamodbError};
+use crate::workflow::Workflow;
+use anyhow::Context;
+use chrono::Utc;
+use tokio::sync::OnceCell;
+
+pub struct Client {
+    table: DynamodbClient,
+}
+
+impl Client {
+    async fn new() -> Client {
+        let table_name = std::env::var("DYNAMODB_ENVIRONMENTS")
+            .expect("DYNAMODB_ENVIRONMENTS is required but not set");
+
+        Client {
+            table: DynamodbClient::new(config().await, table_name),
+        }
+    }
+
+    /// Records the workflow as live in its environment at `idx`. Workflows can finish out
+    /// of order, so this is ignored if a newer workflow is already live there.
+    pub async fn record(&self, workflow: &Workflow, idx: usize) -> Result<(), anyhow::Error> {
+        let Some(environment) = workflow.environments.get(idx) else {
+            anyhow::bail!("workflow has no environment at {idx}");
+        };
+
+        let res = self
+            .table
+            .run_update::<LiveEnvironment>(
+                self.table
+                    .update()
+                    .key("id", to_attribute_value(&workflow.id)?)
+                    .key("name", to_attribute_value(&environment.name)?)
+                    .update_expression("SET #position = :position, #sha = :sha, #git_ref = :git_ref, #commit_message = :commit_message, #workflow_created_at = :workflow_created_at, #live_at = :live_at")
+                    .condition_expression("attribute_not_exists(#workflow_created_at) or #workflow_created_at <= :workflow_created_at")
+                    .expression_attribute_names("#position", "position")
+                    .expression_attribute_names("#sha", "sha")
+                    .expression_attribute_names("#git_ref", "git_ref")
+                    .expression_attribute_names("#commit_message", "commit_message")
+                    .expression_attribute_names("#workflow_created_at", "workflow_created_at")
+                    .expression_attribute_names("#live_at", "live_at")
+                    .expression_attribute_values(":position", to_attribute_value(idx)?)
+                    .expression_attribute_values(":sha", to_attribute_value(&workflow.sha)?)
+                    .expression_attribute_values(":git_ref", to_attribute_value(&workflow.git_ref)?)
+                    .expression_attribute_values(
+                        ":commit_message",
+                        to_attribute_value(&workflow.commit_message)?,
+                    )
+                    .expression_attribute_values(
+                        ":workflow_created_at",
+                        to_attribute_value(workflow.created_at.to_rfc3339())?,
+                    )
+                    .expression_attribute_values(
+                        ":live_at",
+                        to_attribute_value(environment.finished_at.unwrap_or_else(Utc::now))?,
+                    ),
+            )
+            .await;
+
+        match res {
+            Ok(_) => Ok(()),
+            Err(DynamodbError::NotFound()) => {
+                log::info!(
+                    "not recording {} as live in {}, a newer workflow already is",
+                    workflow.sha,
+                    environment.name
+                );
+                Ok(())
+            }
+            Err(e) => Err(e).context("recording live environment"),
+        }
+    }
+
+    /// Lists what is live in each environment of the repository, in the order they are
+    /// deployed to.
+    pub async fn list(
+        &self,
+        owner: &str,
+        repo: &str,
+    ) -> Result<Vec<LiveEnvironment>, anyhow::Error> {
+        let mut environments: Vec<LiveEnvironment> = self
+            .table
+            .run_query(
+                self.table
+                    .query()
+                    .key_condition_expression("#id = :id")
+                    .expression_attribute_names("#id", "id")
+                    .expression_attribute_values(
+                        ":id",
+                        to_attribute_value(format!("{owner}/{repo}"))?,
+                    ),
+            )
+            .await
+            .context("list environments")?;
+
+        environments.sort_by_key(|e| e.position);
+        Ok(environments)
+    }
+}
+
+pub async fn client() -> &'static Client {
+    static CONFIG: OnceCell<Client> = OnceCell::const_new();
+    CONFIG.get_or_init(Client::new).await
+}
@@ -0,0 +1,26 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::workflow::CreatedAt;
+
+#[cfg(feature = "ssr")]
+mod client;
+
+#[cfg(feature = "ssr")]
+pub use client::*;
+
+/// What is live in an environment, taken from the workflow that most recently deployed
+/// to it successfully.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LiveEnvironment {
+    /// Composite of owner/repo.
+    pub id: String,
+    pub name: String,
+    /// Where the environment comes in the workflow, with 0 being deployed to first.
+    pub position: usize,
+    pub sha: String,
+    pub git_ref: String,
+    pub commit_message: String,
+    pub workflow_created_at: CreatedAt,
+    pub live_at: DateTime<Utc>,
+}
@@ -0,0 +1,134 @@
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+use crate::auth::{user_repositories, Session};
+use crate::workflow::Workflow;
+use axum::extract::{Path, Request};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use futures::stream::{self, Stream, StreamExt};
+use http::StatusCode;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// Whether the processor runs in this process. Updates are only published by the
+/// processor, so without it a stream would never receive anything.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+fn channel() -> &'static broadcast::Sender<Workflow> {
+    static CHANNEL: OnceLock<broadcast::Sender<Workflow>> = OnceLock::new();
+    CHANNEL.get_or_init(|| broadcast::channel(64).0)
+}
+
+/// Enables streaming updates, which should only be called when the processor runs in
+/// the same process as the server.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Publishes a created or updated workflow to anyone streaming updates for its repo.
+pub(crate) fn publish(workflow: &Workflow) {
+    // Sending only fails when nobody is listening, which is fine.
+    _ = channel().send(workflow.clone());
+}
+
+#[derive(Debug, PartialEq)]
+enum Update {
+    Workflow(Workflow),
+    /// Updates were missed, so the client has to fetch what it shows afresh.
+    Resync,
+}
+
+fn updates(id: String, rx: broadcast::Receiver<Workflow>) -> impl Stream<Item = Update> {
+    stream::unfold(rx, move |mut rx| {
+        let id = id.clone();
+        async move {
+            loop {
+                match rx.recv().await {
+                    Ok(w) if w.id == id => return Some((Update::Workflow(w), rx)),
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(n)) => {
+                        log::info!("event stream lagged by {n} events");
+                        return Some((Update::Resync, rx));
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        }
+    })
+}
+
+fn event(update: Update) -> Option<Result<Event, Infallible>> {
+    match update {
+        Update::Workflow(w) => match Event::default().event("workflow").json_data(&w) {
+            Ok(event) => Some(Ok(event)),
+            Err(e) => {
+                log::error!("failed to serialize workflow event: {:#}", e);
+                None
+            }
+        },
+        Update::Resync => Some(Ok(Event::default().event("resync").data(""))),
+    }
+}
+
+/// Streams workflow updates for a repository as server-sent events. Where streaming
+/// isn't available this responds with an error, which the dashboard takes as its cue to
+/// poll instead.
+pub async fn stream(Path((owner, repo)): Path<(String, String)>, req: Request) -> Response {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return StatusCode::SERVICE_UNAVAILABLE.into_response();
+    }
+
+    let Some(session) = req.extensions().get::<Session>() else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+
+    let id = format!("{owner}/{repo}");
+    match user_repositories(session).await {
+        Ok(repos) if repos.contains(&id) => {}
+        Ok(_) => return StatusCode::FORBIDDEN.into_response(),
+        Err(e) => return e.status_code().into_response(),
+    }
+
+    let events = updates(id, channel().subscribe()).filter_map(|u| async move { event(u) });
+    Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workflow::fixtures::workflow;
+    use crate::workflow::Status;
+    use futures::executor::block_on;
+
+    #[test]
+    fn test_updates_resync_after_lag() {
+        let (tx, rx) = broadcast::channel(2);
+        let mut updates = Box::pin(updates("owner/repo".to_string(), rx));
+
+        let mut other = workflow(Status::Running, vec![]);
+        other.id = "owner/other".to_string();
+        tx.send(other).unwrap();
+        let latest = workflow(Status::Success, vec![]);
+        for w in [
+            workflow(Status::Running, vec![]),
+            workflow(Status::Running, vec![]),
+            latest.clone(),
+        ] {
+            tx.send(w).unwrap();
+        }
+
+        assert_eq!(block_on(updates.next()), Some(Update::Resync));
+        // The receiver carries on from the oldest update it still has.
+        assert!(matches!(
+            block_on(updates.next()),
+            Some(Update::Workflow(_))
+        ));
+        assert_eq!(block_on(updates.next()), Some(Update::Workflow(latest)));
+
+        drop(tx);
+        assert_eq!(block_on(updates.next()), None);
+    }
+}
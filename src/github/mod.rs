@@ -1,10 +1,12 @@
 use anyhow::Context;
+use chrono::{DateTime, Utc};
 use jsonwebtoken::{self, decode, jwk::JwkSet, Algorithm, DecodingKey, Validation};
 use reqwest::{header, Client, StatusCode};
 use serde::{Deserialize, Serialize};
 use tokio::sync::OnceCell;
 
-use crate::workflow::EnvironmentStatus;
+use crate::tokens::Operation;
+use crate::workflow::{EnvironmentStatus, Provenance};
 
 mod token_cache;
 
@@ -25,15 +27,23 @@ pub struct CreateDeploymentRequest<'a> {
     pub git_ref: &'a str,
     pub environment: &'a str,
     pub description: &'a str,
+    /// Contexts GitHub checks have passed before creating the deployment.
+    pub required_contexts: &'a [String],
 }
 
+/// GitHub refused to create a deployment because its required contexts haven't all
+/// passed, which retrying won't change.
+#[derive(thiserror::Error, Debug)]
+#[error("required contexts have not passed: {0}")]
+pub struct RequiredContextsFailed(pub String);
+
 #[derive(Debug, Serialize)]
 struct CreateDeploymentRequestBody<'a> {
     r#ref: &'a str,
     environment: &'a str,
     description: &'a str,
     auto_merge: bool,
-    required_contexts: Vec<String>,
+    required_contexts: &'a [String],
 }
 
 const GITHUB_API_VERSION_HEADER: &str = "X-GitHub-Api-Version";
@@ -74,8 +84,9 @@ pub enum WorkflowStatus {
 
 #[derive(Debug, Deserialize)]
 pub struct Workflow {
-    // pub id: u64,
-    // pub name: String,
+    pub id: u64,
+    pub name: String,
+    pub html_url: String,
     // pub head_sha: String,
     // pub head_branch: String,
     // pub event: String,
@@ -154,11 +165,11 @@ pub async fn create_deployment(
             req.owner, req.repo,
         ))
         .json(&CreateDeploymentRequestBody {
-            description: req.description,
+            description: truncate_description(req.description),
             environment: req.environment,
             r#ref: req.git_ref,
             auto_merge: false,
-            required_contexts: vec![],
+            required_contexts: req.required_contexts,
         })
         .header(header::USER_AGENT, "pipedream")
         .header(header::ACCEPT, "application/vnd.github+json")
@@ -169,6 +180,13 @@ pub async fn create_deployment(
         .context("sending github workflow dispatch request")?;
 
     let status = res.status();
+    if status == StatusCode::CONFLICT {
+        let text = res
+            .text()
+            .await
+            .unwrap_or_else(|_| "no error message".to_string());
+        return Err(RequiredContextsFailed(text).into());
+    }
     if status != StatusCode::ACCEPTED && status != StatusCode::CREATED {
         let text = res
             .text()
@@ -201,29 +219,64 @@ pub enum DeploymentStatus {
     Failure,
     #[serde(rename = "success")]
     Success,
+    #[serde(rename = "error")]
+    Error,
+    #[serde(rename = "inactive")]
+    Inactive,
+    #[serde(rename = "pending")]
+    Pending,
 }
 
 impl From<EnvironmentStatus> for DeploymentStatus {
     fn from(status: EnvironmentStatus) -> Self {
         match status {
-            EnvironmentStatus::Pending | EnvironmentStatus::Queued => DeploymentStatus::Queued,
+            EnvironmentStatus::Pending => DeploymentStatus::Pending,
+            EnvironmentStatus::Queued => DeploymentStatus::Queued,
             EnvironmentStatus::Running => DeploymentStatus::InProgress,
             EnvironmentStatus::Success => DeploymentStatus::Success,
             EnvironmentStatus::Failure => DeploymentStatus::Failure,
+            EnvironmentStatus::Cancelled => DeploymentStatus::Error,
+            EnvironmentStatus::Skipped => DeploymentStatus::Inactive,
         }
     }
 }
 
+/// A deployment status, as shown on the deployment and environment pages on GitHub.
+#[derive(Debug)]
+pub struct DeploymentStatusUpdate {
+    pub state: DeploymentStatus,
+    /// What's happening, e.g. `soaking for 25m`.
+    pub description: String,
+    /// Where to follow the deployment, i.e. its workflow on pipedream.
+    pub log_url: String,
+    /// Where the deployed environment can be reached, if known.
+    pub environment_url: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
-struct UpdateStatusRequestBody {
-    state: DeploymentStatus,
+struct UpdateStatusRequestBody<'a> {
+    state: &'a DeploymentStatus,
+    description: &'a str,
+    log_url: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    environment_url: Option<&'a str>,
+}
+
+/// GitHub rejects descriptions longer than this many characters.
+const MAX_DESCRIPTION_LENGTH: usize = 140;
+
+fn truncate_description(description: &str) -> &str {
+    match description.char_indices().nth(MAX_DESCRIPTION_LENGTH) {
+        Some((idx, _)) => &description[..idx],
+        None => description,
+    }
 }
 
 pub async fn update_deployment_status(
     owner: &str,
     repo: &str,
     deployment_id: &u64,
-    status: DeploymentStatus,
+    status: &DeploymentStatusUpdate,
 ) -> Result<(), anyhow::Error> {
     let token = get_token(owner, repo).await?;
     let res = http()
@@ -232,7 +285,12 @@ pub async fn update_deployment_status(
             "https://api.github.com/repos/{}/{}/deployments/{}/statuses",
             owner, repo, deployment_id
         ))
-        .json(&UpdateStatusRequestBody { state: status })
+        .json(&UpdateStatusRequestBody {
+            state: &status.state,
+            description: truncate_description(&status.description),
+            log_url: &status.log_url,
+            environment_url: status.environment_url.as_deref(),
+        })
         .header(header::USER_AGENT, "pipedream")
         .header(header::ACCEPT, "application/vnd.github+json")
         .header(header::AUTHORIZATION, format!("Bearer {}", token))
@@ -258,6 +316,438 @@ pub async fn update_deployment_status(
     Ok(())
 }
 
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CommitState {
+    Error,
+    Failure,
+    Pending,
+    Success,
+}
+
+/// The latest status reported for a context on a commit.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CommitStatus {
+    pub context: String,
+    pub state: CommitState,
+}
+
+#[derive(Debug, Deserialize)]
+struct CombinedStatusResponse {
+    statuses: Vec<CommitStatus>,
+}
+
+/// Lists the latest status of each context on a commit.
+pub async fn list_commit_statuses(
+    owner: &str,
+    repo: &str,
+    sha: &str,
+) -> Result<Vec<CommitStatus>, anyhow::Error> {
+    let token = get_token(owner, repo).await?;
+    let res = http()
+        .await
+        .get(format!(
+            "https://api.github.com/repos/{}/{}/commits/{}/status",
+            owner, repo, sha
+        ))
+        .query(&[("per_page", "100")])
+        .header(header::USER_AGENT, "pipedream")
+        .header(header::ACCEPT, "application/vnd.github+json")
+        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+        .header(GITHUB_API_VERSION_HEADER, GITHUB_API_VERSION)
+        .send()
+        .await
+        .context("getting github commit status")?;
+
+    let status = res.status();
+    if status != StatusCode::OK {
+        let text = res
+            .text()
+            .await
+            .unwrap_or_else(|_| "no error message".to_string());
+        log::error!(
+            "failed to get status of {owner}/{repo} at {sha}, status={}, text={text}",
+            status.as_u16()
+        );
+        return Err(anyhow::anyhow!("failed to get github commit status"));
+    }
+
+    let response = res
+        .json::<CombinedStatusResponse>()
+        .await
+        .context("parsing github commit status response")?;
+
+    Ok(response.statuses)
+}
+
+/// The latest run of a check on a commit. `conclusion` is only set once `status` is
+/// `completed`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CheckRun {
+    pub id: u64,
+    pub name: String,
+    pub status: String,
+    pub conclusion: Option<String>,
+    #[serde(default)]
+    pub external_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListCheckRunsResponse {
+    total_count: usize,
+    check_runs: Vec<CheckRun>,
+}
+
+/// The most check runs GitHub returns at once.
+const CHECK_RUNS_PAGE_SIZE: usize = 100;
+
+/// Lists the check runs on a commit matching the query, a page at a time until there are
+/// no more.
+async fn fetch_check_runs(
+    owner: &str,
+    repo: &str,
+    sha: &str,
+    query: &[(&str, &str)],
+) -> Result<Vec<CheckRun>, anyhow::Error> {
+    let token = get_token(owner, repo).await?;
+    let per_page = CHECK_RUNS_PAGE_SIZE.to_string();
+    let mut runs = vec![];
+    for page in 1.. {
+        let res = http()
+            .await
+            .get(format!(
+                "https://api.github.com/repos/{}/{}/commits/{}/check-runs",
+                owner, repo, sha
+            ))
+            .query(query)
+            .query(&[("per_page", per_page.as_str()), ("page", &page.to_string())])
+            .header(header::USER_AGENT, "pipedream")
+            .header(header::ACCEPT, "application/vnd.github+json")
+            .header(header::AUTHORIZATION, format!("Bearer {}", token))
+            .header(GITHUB_API_VERSION_HEADER, GITHUB_API_VERSION)
+            .send()
+            .await
+            .context("getting github check runs")?;
+
+        let status = res.status();
+        if status != StatusCode::OK {
+            let text = res
+                .text()
+                .await
+                .unwrap_or_else(|_| "no error message".to_string());
+            log::error!(
+                "failed to list check runs of {owner}/{repo} at {sha}, status={}, text={text}",
+                status.as_u16()
+            );
+            return Err(anyhow::anyhow!("failed to list github check runs"));
+        }
+
+        let response = res
+            .json::<ListCheckRunsResponse>()
+            .await
+            .context("parsing github check runs response")?;
+
+        let fetched = response.check_runs.len();
+        runs.extend(response.check_runs);
+        if fetched < CHECK_RUNS_PAGE_SIZE || runs.len() >= response.total_count {
+            break;
+        }
+    }
+
+    Ok(runs)
+}
+
+/// Lists the latest run of each check on a commit.
+pub async fn list_check_runs(
+    owner: &str,
+    repo: &str,
+    sha: &str,
+) -> Result<Vec<CheckRun>, anyhow::Error> {
+    fetch_check_runs(owner, repo, sha, &[("filter", "latest")]).await
+}
+
+/// Lists every run of the named check on a commit, not just the latest.
+pub async fn list_named_check_runs(
+    owner: &str,
+    repo: &str,
+    sha: &str,
+    name: &str,
+) -> Result<Vec<CheckRun>, anyhow::Error> {
+    fetch_check_runs(owner, repo, sha, &[("filter", "all"), ("check_name", name)]).await
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckRunStatus {
+    Queued,
+    InProgress,
+    Completed,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckRunConclusion {
+    Success,
+    Failure,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct CheckRunOutput {
+    pub title: String,
+    /// Markdown, shown on the check run's page.
+    pub summary: String,
+}
+
+/// The state of a check run, as sent when creating or updating it.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct CheckRunUpdate {
+    pub name: String,
+    pub status: CheckRunStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conclusion: Option<CheckRunConclusion>,
+    pub details_url: String,
+    pub external_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<DateTime<Utc>>,
+    pub output: CheckRunOutput,
+}
+
+#[derive(Debug, Serialize)]
+struct CreateCheckRunRequestBody<'a> {
+    head_sha: &'a str,
+    #[serde(flatten)]
+    check_run: &'a CheckRunUpdate,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateCheckRunResponse {
+    id: u64,
+}
+
+/// Creates a check run on a commit, returning its id.
+pub async fn create_check_run(
+    owner: &str,
+    repo: &str,
+    sha: &str,
+    check_run: &CheckRunUpdate,
+) -> Result<u64, anyhow::Error> {
+    let token = get_token(owner, repo).await?;
+    let res = http()
+        .await
+        .post(format!(
+            "https://api.github.com/repos/{}/{}/check-runs",
+            owner, repo
+        ))
+        .json(&CreateCheckRunRequestBody {
+            head_sha: sha,
+            check_run,
+        })
+        .header(header::USER_AGENT, "pipedream")
+        .header(header::ACCEPT, "application/vnd.github+json")
+        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+        .header(GITHUB_API_VERSION_HEADER, GITHUB_API_VERSION)
+        .send()
+        .await
+        .context("creating check run")?;
+
+    let status = res.status();
+    if status != StatusCode::CREATED {
+        let text = res
+            .text()
+            .await
+            .unwrap_or_else(|_| "no error message".to_string());
+        log::info!(
+            "failed to create check run for {owner}/{repo} at {sha}, status={}, text={text}",
+            status.as_u16()
+        );
+        return Err(anyhow::anyhow!("failed to create github check run"));
+    }
+
+    let response = res
+        .json::<CreateCheckRunResponse>()
+        .await
+        .context("parsing github check run response")?;
+
+    Ok(response.id)
+}
+
+pub async fn update_check_run(
+    owner: &str,
+    repo: &str,
+    check_run_id: u64,
+    check_run: &CheckRunUpdate,
+) -> Result<(), anyhow::Error> {
+    let token = get_token(owner, repo).await?;
+    let res = http()
+        .await
+        .patch(format!(
+            "https://api.github.com/repos/{}/{}/check-runs/{}",
+            owner, repo, check_run_id
+        ))
+        .json(check_run)
+        .header(header::USER_AGENT, "pipedream")
+        .header(header::ACCEPT, "application/vnd.github+json")
+        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+        .header(GITHUB_API_VERSION_HEADER, GITHUB_API_VERSION)
+        .send()
+        .await
+        .context("updating check run")?;
+
+    let status = res.status();
+    if status != StatusCode::OK {
+        let text = res
+            .text()
+            .await
+            .unwrap_or_else(|_| "no error message".to_string());
+        log::info!(
+            "failed to update check run {check_run_id} for {owner}/{repo}, status={}, text={text}",
+            status.as_u16()
+        );
+        return Err(anyhow::anyhow!("failed to update github check run"));
+    }
+
+    Ok(())
+}
+
+/// Gets the contents of a file in the repository at a git ref, or `None` if there is no
+/// such file.
+pub async fn get_file_contents(
+    owner: &str,
+    repo: &str,
+    path: &str,
+    git_ref: &str,
+) -> Result<Option<String>, anyhow::Error> {
+    let token = get_token(owner, repo).await?;
+    let res = http()
+        .await
+        .get(format!(
+            "https://api.github.com/repos/{}/{}/contents/{}",
+            owner, repo, path
+        ))
+        .query(&[("ref", git_ref)])
+        .header(header::USER_AGENT, "pipedream")
+        .header(header::ACCEPT, "application/vnd.github.raw+json")
+        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+        .header(GITHUB_API_VERSION_HEADER, GITHUB_API_VERSION)
+        .send()
+        .await
+        .context("getting github file contents")?;
+
+    let status = res.status();
+    if status == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if status != StatusCode::OK {
+        let text = res
+            .text()
+            .await
+            .unwrap_or_else(|_| "no error message".to_string());
+        log::error!(
+            "failed to get {path} of {owner}/{repo} at {git_ref}, status={}, text={text}",
+            status.as_u16()
+        );
+        return Err(anyhow::anyhow!("failed to get github file contents"));
+    }
+
+    res.text()
+        .await
+        .map(Some)
+        .context("reading github file contents")
+}
+
+#[derive(Debug, Deserialize)]
+struct CompareResponse {
+    status: String,
+}
+
+/// Whether the commit is on the repository's branch, the branch's head or behind it.
+/// Commits from forks are only reachable through pull requests, so aren't.
+pub async fn is_commit_on_branch(
+    owner: &str,
+    repo: &str,
+    sha: &str,
+    branch: &str,
+) -> Result<bool, anyhow::Error> {
+    let token = get_token(owner, repo).await?;
+    let res = http()
+        .await
+        .get(format!(
+            "https://api.github.com/repos/{}/{}/compare/{}...{}",
+            owner, repo, sha, branch
+        ))
+        .query(&[("per_page", "1")])
+        .header(header::USER_AGENT, "pipedream")
+        .header(header::ACCEPT, "application/vnd.github+json")
+        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+        .header(GITHUB_API_VERSION_HEADER, GITHUB_API_VERSION)
+        .send()
+        .await
+        .context("comparing github commits")?;
+
+    let status = res.status();
+    if status == StatusCode::NOT_FOUND {
+        return Ok(false);
+    }
+    if status != StatusCode::OK {
+        let text = res
+            .text()
+            .await
+            .unwrap_or_else(|_| "no error message".to_string());
+        log::error!(
+            "failed to compare {sha} with {branch} of {owner}/{repo}, status={}, text={text}",
+            status.as_u16()
+        );
+        return Err(anyhow::anyhow!("failed to compare github commits"));
+    }
+
+    let response = res
+        .json::<CompareResponse>()
+        .await
+        .context("parsing github compare response")?;
+
+    // The branch is compared against the commit, so it contains it unless it diverged.
+    Ok(response.status == "identical" || response.status == "ahead")
+}
+
+pub async fn cancel_workflow_run(
+    owner: &str,
+    repo: &str,
+    run_id: u64,
+) -> Result<(), anyhow::Error> {
+    let token = get_token(owner, repo).await?;
+    let res = http()
+        .await
+        .post(format!(
+            "https://api.github.com/repos/{}/{}/actions/runs/{}/cancel",
+            owner, repo, run_id
+        ))
+        .header(header::USER_AGENT, "pipedream")
+        .header(header::ACCEPT, "application/vnd.github+json")
+        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+        .header(GITHUB_API_VERSION_HEADER, GITHUB_API_VERSION)
+        .send()
+        .await
+        .context("cancelling github workflow run")?;
+
+    let status = res.status();
+    if status != StatusCode::ACCEPTED {
+        let text = res
+            .text()
+            .await
+            .unwrap_or_else(|_| "no error message".to_string());
+        log::info!(
+            "failed to cancel workflow run {run_id} for {owner}/{repo}, status={}, text={text}",
+            status.as_u16()
+        );
+        return Err(anyhow::anyhow!("failed to cancel github workflow run"));
+    }
+
+    Ok(())
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(untagged)]
 enum OAuthResponse {
@@ -277,22 +767,36 @@ pub struct OauthTokenResponse {
     pub access_token: String,
     pub expires_in: i64, // number of seconds until expiration
     pub refresh_token: String,
-    // pub refresh_token_expires_in: u64,
+    pub refresh_token_expires_in: Option<i64>,
     // pub scope: String,
     // pub token_type: String,
 }
 
 pub async fn exchange_oauth_token(code: &str) -> Result<OauthTokenResponse, anyhow::Error> {
+    request_oauth_token(&[("code", code)]).await
+}
+
+pub async fn refresh_oauth_token(refresh_token: &str) -> Result<OauthTokenResponse, anyhow::Error> {
+    request_oauth_token(&[
+        ("grant_type", "refresh_token"),
+        ("refresh_token", refresh_token),
+    ])
+    .await
+}
+
+async fn request_oauth_token(params: &[(&str, &str)]) -> Result<OauthTokenResponse, anyhow::Error> {
     let client_id = std::env::var("GITHUB_CLIENT_ID").unwrap();
     let client_secret = std::env::var("GITHUB_CLIENT_SECRET").unwrap();
+    let mut form = vec![
+        ("client_id", client_id.as_str()),
+        ("client_secret", client_secret.as_str()),
+    ];
+    form.extend_from_slice(params);
+
     let res = http()
         .await
         .post("https://github.com/login/oauth/access_token")
-        .form(&[
-            ("client_id", client_id),
-            ("client_secret", client_secret),
-            ("code", code.to_owned()),
-        ])
+        .form(&form)
         .header(header::USER_AGENT, "pipedream")
         .header(header::ACCEPT, "application/json")
         .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
@@ -446,19 +950,192 @@ pub async fn list_user_installations(token: &str) -> Result<Vec<Installation>, a
     Ok(response.installations)
 }
 
+#[derive(Debug, Deserialize)]
+pub struct User {
+    pub id: i64,
+    pub login: String,
+}
+
+pub async fn get_user(token: &str) -> Result<User, anyhow::Error> {
+    let res = http()
+        .await
+        .get("https://api.github.com/user")
+        .header(header::USER_AGENT, "pipedream")
+        .header(header::ACCEPT, "application/vnd.github+json")
+        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+        .header(GITHUB_API_VERSION_HEADER, GITHUB_API_VERSION)
+        .send()
+        .await
+        .context("getting user")?;
+
+    let status = res.status();
+    if status != StatusCode::OK {
+        let text = res
+            .text()
+            .await
+            .unwrap_or_else(|_| "no error message".to_string());
+
+        log::info!(
+            "failed to get user, status={}, text={}",
+            status.clone().as_u16(),
+            text
+        );
+        return Err(anyhow::anyhow!("failed to get user"));
+    }
+
+    let response = res
+        .json::<User>()
+        .await
+        .context("parsing github user response")?;
+
+    Ok(response)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RepositoryPermissions {
+    pub admin: bool,
+    #[serde(default)]
+    pub maintain: bool,
+    pub push: bool,
+    #[serde(default)]
+    pub triage: bool,
+    pub pull: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct RepositoryResponse {
+    permissions: RepositoryPermissions,
+}
+
+/// Gets the permissions the user the token belongs to has on the repository.
+pub async fn get_repository_permissions(
+    token: &str,
+    owner: &str,
+    repo: &str,
+) -> Result<RepositoryPermissions, anyhow::Error> {
+    let res = http()
+        .await
+        .get(format!("https://api.github.com/repos/{}/{}", owner, repo))
+        .header(header::USER_AGENT, "pipedream")
+        .header(header::ACCEPT, "application/vnd.github+json")
+        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+        .header(GITHUB_API_VERSION_HEADER, GITHUB_API_VERSION)
+        .send()
+        .await
+        .context("getting repository")?;
+
+    let status = res.status();
+    if status != StatusCode::OK {
+        let text = res
+            .text()
+            .await
+            .unwrap_or_else(|_| "no error message".to_string());
+
+        log::info!(
+            "failed to get repository, status={}, text={}",
+            status.clone().as_u16(),
+            text
+        );
+        return Err(anyhow::anyhow!("failed to get repository"));
+    }
+
+    let response = res
+        .json::<RepositoryResponse>()
+        .await
+        .context("parsing github repository response")?;
+
+    Ok(response.permissions)
+}
+
+#[derive(Debug, Deserialize)]
+struct MembershipResponse {
+    state: String,
+    role: String,
+}
+
+/// Whether the user is an active admin of the organisation, as seen with the token. Users
+/// outside the organisation, and owners that aren't organisations, aren't admins.
+pub async fn is_org_admin(token: &str, org: &str, user: &str) -> Result<bool, anyhow::Error> {
+    let res = http()
+        .await
+        .get(format!(
+            "https://api.github.com/orgs/{}/memberships/{}",
+            org, user
+        ))
+        .header(header::USER_AGENT, "pipedream")
+        .header(header::ACCEPT, "application/vnd.github+json")
+        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+        .header(GITHUB_API_VERSION_HEADER, GITHUB_API_VERSION)
+        .send()
+        .await
+        .context("getting organisation membership")?;
+
+    let status = res.status();
+    if status == StatusCode::NOT_FOUND || status == StatusCode::FORBIDDEN {
+        return Ok(false);
+    }
+    if status != StatusCode::OK {
+        let text = res
+            .text()
+            .await
+            .unwrap_or_else(|_| "no error message".to_string());
+        log::info!(
+            "failed to get membership of {user} in {org}, status={}, text={text}",
+            status.as_u16()
+        );
+        return Err(anyhow::anyhow!("failed to get organisation membership"));
+    }
+
+    let response = res
+        .json::<MembershipResponse>()
+        .await
+        .context("parsing github membership response")?;
+
+    Ok(response.state == "active" && response.role == "admin")
+}
+
 #[derive(Debug, Deserialize)]
 struct JWKResponse {
     keys: Vec<jsonwebtoken::jwk::Jwk>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct Claims {
-    sub: String,
-    aud: String,
-    iss: String,
+pub struct Claims {
+    pub sub: String,
+    pub aud: String,
+    pub iss: String,
+    pub actor: String,
+    pub repository: String,
+    pub run_id: String,
+    pub workflow_ref: String,
+    pub event_name: String,
+    #[serde(rename = "ref")]
+    pub git_ref: String,
+}
+
+impl Claims {
+    /// Whether the job the token was issued to may perform the operation on the
+    /// repository. Jobs only get to create workflows for their own repository, so
+    /// anything else, like cancelling, needs an API token.
+    pub fn allows(&self, owner: &str, repo: &str, operation: Operation) -> bool {
+        self.repository == format!("{owner}/{repo}") && operation == Operation::CreateWorkflow
+    }
 }
 
-pub async fn validate_oidc_token(token: &str, owner: &str) -> Result<(), anyhow::Error> {
+impl From<Claims> for Provenance {
+    fn from(claims: Claims) -> Self {
+        Provenance {
+            actor: claims.actor,
+            repository: claims.repository,
+            run_id: claims.run_id,
+            workflow_ref: claims.workflow_ref,
+            event_name: claims.event_name,
+            git_ref: claims.git_ref,
+        }
+    }
+}
+
+pub async fn validate_oidc_token(token: &str, owner: &str) -> Result<Claims, anyhow::Error> {
     let res = http()
         .await
         .get("https://token.actions.githubusercontent.com/.well-known/jwks")
@@ -502,14 +1179,45 @@ pub async fn validate_oidc_token(token: &str, owner: &str) -> Result<(), anyhow:
     validation.set_issuer(&["https://token.actions.githubusercontent.com"]);
     validation.set_audience(&[format!("https://github.com/{owner}")]);
 
-    decode::<Claims>(token, &key, &validation).context("decoding token")?;
+    let token = decode::<Claims>(token, &key, &validation).context("decoding token")?;
 
-    Ok(())
+    Ok(token.claims)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::JWKResponse;
+    use super::{truncate_description, Claims, JWKResponse, MAX_DESCRIPTION_LENGTH};
+    use crate::tokens::Operation;
+
+    #[test]
+    fn test_truncate_description() {
+        assert_eq!(truncate_description("soaking for 25m"), "soaking for 25m");
+
+        let long = "é".repeat(MAX_DESCRIPTION_LENGTH + 10);
+        assert_eq!(
+            truncate_description(&long).chars().count(),
+            MAX_DESCRIPTION_LENGTH
+        );
+    }
+
+    #[test]
+    fn test_claims_allows() {
+        let claims = Claims {
+            sub: "repo:doug/pipedream:ref:refs/heads/main".to_string(),
+            aud: "https://github.com/doug".to_string(),
+            iss: "https://token.actions.githubusercontent.com".to_string(),
+            actor: "doug".to_string(),
+            repository: "doug/pipedream".to_string(),
+            run_id: "42".to_string(),
+            workflow_ref: "doug/pipedream/.github/workflows/ci.yml@refs/heads/main".to_string(),
+            event_name: "push".to_string(),
+            git_ref: "refs/heads/main".to_string(),
+        };
+
+        assert!(claims.allows("doug", "pipedream", Operation::CreateWorkflow));
+        assert!(!claims.allows("doug", "other", Operation::CreateWorkflow));
+        assert!(!claims.allows("doug", "pipedream", Operation::CancelWorkflow));
+    }
 
     #[tokio::test]
     async fn test_decoding_jwk() {
@@ -4,14 +4,31 @@ pub mod auth;
 #[cfg(feature = "ssr")]
 pub mod aws;
 pub mod blocks;
+#[cfg(feature = "ssr")]
+pub(crate) mod crypto;
+pub mod environments;
 pub mod error_template;
 #[cfg(feature = "ssr")]
+pub mod events;
+#[cfg(feature = "ssr")]
 pub mod fileserv;
 #[cfg(feature = "ssr")]
 pub(crate) mod github;
 mod pages;
+pub mod settings;
+pub mod tokens;
+#[cfg(feature = "ssr")]
+pub mod webhooks;
 pub mod workflow;
 
+/// The URL pipedream is served from, e.g. `https://pipedream.example.com`.
+#[cfg(feature = "ssr")]
+pub(crate) fn base_url() -> String {
+    std::env::var("DOMAIN")
+        .map(|d| format!("https://{d}"))
+        .unwrap_or("http://127.0.0.1:3000".to_string())
+}
+
 cfg_if! { if #[cfg(feature = "hydrate")] {
     use leptos::*;
     use wasm_bindgen::prelude::wasm_bindgen;
@@ -1,11 +1,17 @@
 #[cfg(feature = "ssr")]
 #[tokio::main]
 async fn main() {
-    use axum::Router;
+    use axum::{
+        routing::{get, post},
+        Router,
+    };
     use leptos::*;
     use leptos_axum::{generate_route_list, LeptosRoutes};
     use pipedream::app::*;
+    use pipedream::auth;
+    use pipedream::events;
     use pipedream::fileserv::file_and_error_handler;
+    use pipedream::webhooks;
     use pipedream::workflow;
     use tokio::time::{sleep, Duration};
 
@@ -22,6 +28,7 @@ async fn main() {
             log::warn!("not starting worker due to missing LEPTOS_WORKER env");
             return;
         }
+        events::enable();
         loop {
             if let Err(e) = workflow::process_workflows(client).await {
                 log::error!("error processing workflows: {0:#}", e);
@@ -32,7 +39,11 @@ async fn main() {
 
     let addr = leptos_options.site_addr;
     let app = Router::new()
+        .route("/api/events/:owner/:repo", get(events::stream))
+        .route(webhooks::WEBHOOK_PATH, post(webhooks::handle))
         .leptos_routes(&leptos_options, routes, App)
+        // Only routed requests need the session, not the static files the fallback serves.
+        .route_layer(axum::middleware::from_fn(auth::layer))
         .fallback(file_and_error_handler)
         .with_state(leptos_options);
 
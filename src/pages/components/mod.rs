@@ -1 +1,200 @@
+use leptos::*;
+use leptos_router::{ActionForm, A};
 
+use crate::auth::Logout;
+use crate::workflow::Workflow;
+
+#[server(ListRepos)]
+pub async fn list_repos() -> Result<Vec<String>, ServerFnError> {
+    use http::{header, HeaderMap};
+    use leptos_axum::extract;
+
+    let headers: HeaderMap = extract().await?;
+    let session = crate::auth::require_user().map_err(|e| {
+        log::info!("no user found, redirecting to login");
+        // Send the user back to the page they were on once they've logged in.
+        let return_to = headers
+            .get(header::REFERER)
+            .and_then(|r| r.to_str().ok())
+            .and_then(|r| reqwest::Url::parse(r).ok())
+            .map(|r| r.path().to_string());
+        match (return_to, reqwest::Url::parse(&crate::base_url())) {
+            (Some(return_to), Ok(mut url)) => {
+                url.query_pairs_mut().append_pair("return_to", &return_to);
+                leptos_axum::redirect(url.as_str());
+            }
+            _ => leptos_axum::redirect("/"),
+        }
+        e
+    })?;
+
+    Ok(crate::auth::user_repositories(&session).await?)
+}
+
+#[component]
+pub fn SelectOption(is: String, value: ReadSignal<String>) -> impl IntoView {
+    let v = is.clone();
+    view! {
+        <option value=&v selected=move || value() == is>
+            {v}
+        </option>
+    }
+}
+
+#[component]
+pub fn Header() -> impl IntoView {
+    let logout = create_server_action::<Logout>();
+
+    view! {
+        <header class="p-6 bg-white shadow dark:bg-gray-900">
+            <div class="flex justify-between max-w-6xl mx-auto w-full">
+                <nav class="flex flex-row gap-8">
+                    <A href="/overview" class="flex items-center">
+                        Overview
+                    </A>
+                    <A href="/dashboard" class="flex items-center">
+                        Deployments
+                    </A>
+                    <A href="/environments" class="flex items-center">
+                        Environments
+                    </A>
+                </nav>
+                <ActionForm class="flex items-center gap-4" action=logout>
+                    <button type="submit" class="text-sm">
+                        Logout
+                    </button>
+                </ActionForm>
+            </div>
+        </header>
+    }
+}
+
+/// Lets the user pick one of their repositories, picking the first if none is selected.
+#[component]
+pub fn RepoSelect(
+    repo: ReadSignal<String>,
+    /// Called with the `owner/repo` the user picked.
+    #[prop(into)]
+    on_change: Callback<String>,
+) -> impl IntoView {
+    let repos = create_local_resource(move || (), |_| list_repos());
+
+    create_effect(move |_| {
+        if repo.get().is_empty() {
+            if let Some(first) = repos
+                .get()
+                .and_then(|r| r.ok())
+                .and_then(|r| r.first().cloned())
+            {
+                on_change.call(first);
+            }
+        }
+    });
+
+    view! {
+        <Transition fallback=move || {
+            view! {
+                <select class="min-w-24 max-w-48 flex h-10 w-full items-center justify-between rounded-md border border-input bg-white dark:bg-gray-900 px-3 py-2 text-sm focus:outline-none focus:ring-2 focus:ring-ring focus:ring-offset-2 disabled:cursor-not-allowed disabled:opacity-50">
+                    "Loading..."
+                </select>
+            }
+        }>
+
+            {move || {
+                repos
+                    .get()
+                    .map(|repos| match repos {
+                        Ok(repos) => {
+                            view! {
+                                <select
+                                    class="flex h-10 items-center justify-between rounded-md border border-input bg-white dark:bg-gray-900 px-3 py-2 text-sm ring-offset-background placeholder:text-muted-foreground focus:outline-none focus:ring-2 focus:ring-ring focus:ring-offset-2 disabled:cursor-not-allowed disabled:opacity-50"
+                                    on:change=move |ev| {
+                                        on_change.call(event_target_value(&ev));
+                                    }
+                                >
+
+                                    <For each=move || repos.clone() key=|r| r.clone() let:child>
+                                        <SelectOption is=child value=repo/>
+                                    </For>
+                                </select>
+                            }
+                                .into_view()
+                        }
+                        Err(e) => {
+                            view! { <p>Something went wrong: {format!("{e}")}</p> }
+                                .into_view()
+                        }
+                    })
+            }}
+
+        </Transition>
+    }
+}
+
+/// Streams updates to the workflows of `repo` from the server, calling `on_update` with
+/// each created or changed workflow, and `on_resync` when the server dropped updates so
+/// whatever is shown should be fetched again. The returned signal is true while the
+/// stream is connected; when it isn't, for example on Vercel where responses can't be
+/// streamed, callers should poll instead.
+pub fn use_workflow_events(
+    repo: Signal<String>,
+    on_update: impl Fn(Workflow) + Copy + 'static,
+    on_resync: impl Fn() + Copy + 'static,
+) -> ReadSignal<bool> {
+    let (connected, set_connected) = create_signal(false);
+
+    #[cfg(feature = "hydrate")]
+    create_effect(move |_| {
+        use wasm_bindgen::{closure::Closure, JsCast};
+        use web_sys::{EventSource, MessageEvent};
+
+        set_connected(false);
+        let (owner, repo) = super::split_repo(&repo.get());
+        if owner.is_empty() || repo.is_empty() {
+            return;
+        }
+
+        let Ok(source) = EventSource::new(&format!("/api/events/{owner}/{repo}")) else {
+            return;
+        };
+
+        let on_open = Closure::<dyn Fn()>::new(move || set_connected(true));
+        let on_message = Closure::<dyn Fn(MessageEvent)>::new(move |ev: MessageEvent| {
+            let workflow = ev
+                .data()
+                .as_string()
+                .and_then(|data| serde_json::from_str::<Workflow>(&data).ok());
+            if let Some(workflow) = workflow {
+                on_update(workflow);
+            }
+        });
+        let on_resync = Closure::<dyn Fn()>::new(on_resync);
+        // Don't let the browser keep retrying, fall back to polling instead.
+        let on_error = Closure::<dyn Fn()>::new({
+            let source = source.clone();
+            move || {
+                set_connected(false);
+                source.close();
+            }
+        });
+
+        source.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+        source
+            .add_event_listener_with_callback("workflow", on_message.as_ref().unchecked_ref())
+            .expect("event listener to be added");
+        source
+            .add_event_listener_with_callback("resync", on_resync.as_ref().unchecked_ref())
+            .expect("event listener to be added");
+        source.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+
+        on_cleanup(move || {
+            source.close();
+            drop((on_open, on_message, on_resync, on_error));
+        });
+    });
+
+    #[cfg(not(feature = "hydrate"))]
+    let _ = (repo, on_update, on_resync, set_connected);
+
+    connected
+}
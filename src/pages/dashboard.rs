@@ -1,25 +1,42 @@
-use crate::auth::Logout;
+use super::components::{use_workflow_events, Header, RepoSelect};
+use super::matrix::Matrix;
+use super::roles::RoleGrants;
+use super::settings::{BranchRules, Directives, ProtectedEnvironments, RequiredChecks};
+use super::split_repo;
+use super::tokens::ApiTokens;
+use crate::auth::Action;
+use crate::blocks::Block;
 use crate::workflow;
-use crate::workflow::{Environment, EnvironmentStatus, Workflow};
-use chrono::{DateTime, Local};
+use crate::workflow::{
+    Environment, EnvironmentStatus, Status, Workflow, WorkflowFilter, WorkflowPage,
+};
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, Utc};
 use leptos::html::Dialog;
 use leptos::*;
 use leptos_meta::Title;
-use leptos_router::{ActionForm, A};
+use leptos_router::{use_navigate, use_params_map, NavigateOptions, A};
 use std::time::Duration;
 
+const PAGE_SIZE: usize = 20;
+
 #[server(ListWorkflows)]
 pub async fn list_workflows(
     owner: String,
     repo: String,
-) -> Result<Vec<workflow::Workflow>, ServerFnError> {
+    filter: WorkflowFilter,
+    cursor: Option<String>,
+) -> Result<WorkflowPage, ServerFnError> {
     if owner.is_empty() || repo.is_empty() {
-        return Ok(vec![]);
+        return Ok(WorkflowPage::default());
     }
 
-    // TODO - authentication middleware. Currently this is unauthenticated. .
+    crate::auth::require_repo_access(&owner, &repo).await?;
 
-    match workflow::client().await.list(owner, repo).await {
+    match workflow::client()
+        .await
+        .list(&owner, &repo, &filter, cursor, PAGE_SIZE)
+        .await
+    {
         Err(e) => {
             log::error!("failed to list workflows: {:#}", e);
             Err(ServerFnError::new("unable to list workflows"))
@@ -28,70 +45,736 @@ pub async fn list_workflows(
     }
 }
 
-#[server(ListRepos)]
-pub async fn list_repos() -> Result<Vec<String>, ServerFnError> {
-    use crate::github;
-    use anyhow::Context;
-    use http::StatusCode;
-    use leptos_axum::extract;
-
-    let response = expect_context::<leptos_axum::ResponseOptions>();
-    let jar: axum_extra::extract::CookieJar = extract().await?;
-
-    // TODO - refresh tokens, expired access tokens... :shrug:
-    let access_token = match jar.get("access") {
-        Some(v) => v,
-        None => {
-            log::info!("no access token found, redirecting to login");
-            response.set_status(StatusCode::UNAUTHORIZED);
-            leptos_axum::redirect("/");
-            return Err(ServerFnError::new("not authorized"));
+#[server(Cancel)]
+pub async fn cancel(owner: String, repo: String, id: i64) -> Result<(), ServerFnError> {
+    crate::auth::require_action(&owner, &repo, Action::Cancel).await?;
+
+    let created_at =
+        DateTime::from_timestamp(id, 0).ok_or_else(|| ServerFnError::new("invalid workflow id"))?;
+    let w = match workflow::client()
+        .await
+        .get(&owner, &repo, created_at)
+        .await
+    {
+        Ok(Some(w)) => w,
+        Ok(None) => return Err(ServerFnError::new("workflow not found")),
+        Err(e) => {
+            log::error!("failed to get workflow: {:#}", e);
+            return Err(ServerFnError::new("unable to cancel workflow"));
+        }
+    };
+
+    match workflow::cancel(w).await {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(ServerFnError::new("workflow has already finished")),
+        Err(e) => {
+            log::error!("failed to cancel workflow: {:#}", e);
+            Err(ServerFnError::new("unable to cancel workflow"))
+        }
+    }
+}
+
+/// Redeploys the sha of an earlier successful workflow as a new rollback workflow,
+/// returning the path of the new workflow.
+#[server(Redeploy)]
+pub async fn redeploy(
+    owner: String,
+    repo: String,
+    id: i64,
+    environments: Vec<String>,
+    skip_stability: bool,
+) -> Result<String, ServerFnError> {
+    crate::auth::require_action(&owner, &repo, Action::Redeploy).await?;
+
+    let created_at =
+        DateTime::from_timestamp(id, 0).ok_or_else(|| ServerFnError::new("invalid workflow id"))?;
+    let client = workflow::client().await;
+    let original = match client.get(&owner, &repo, created_at).await {
+        Ok(Some(w)) => w,
+        Ok(None) => return Err(ServerFnError::new("workflow not found")),
+        Err(e) => {
+            log::error!("failed to get workflow: {:#}", e);
+            return Err(ServerFnError::new("unable to redeploy workflow"));
+        }
+    };
+    if original.status != workflow::Status::Success {
+        return Err(ServerFnError::new(
+            "only successful workflows can be redeployed",
+        ));
+    }
+
+    let request =
+        workflow::CreateWorkflowRequest::rollback(&original, &environments, skip_stability);
+    if request.environments.is_empty() {
+        return Err(ServerFnError::new("no environments to redeploy to"));
+    }
+
+    match client.create(request).await {
+        Ok(Some(w)) => Ok(w.path()),
+        Ok(None) => Err(ServerFnError::new("unable to redeploy workflow")),
+        Err(e) => {
+            log::error!("failed to create rollback workflow: {:#}", e);
+            Err(ServerFnError::new("unable to redeploy workflow"))
+        }
+    }
+}
+
+/// Retries the environment that failed a workflow, putting the workflow back to running.
+#[server(RetryEnvironment)]
+pub async fn retry_environment(
+    owner: String,
+    repo: String,
+    id: i64,
+    environment: String,
+) -> Result<(), ServerFnError> {
+    crate::auth::require_action(&owner, &repo, Action::Redeploy).await?;
+
+    let created_at =
+        DateTime::from_timestamp(id, 0).ok_or_else(|| ServerFnError::new("invalid workflow id"))?;
+    let client = workflow::client().await;
+    let w = match client.get(&owner, &repo, created_at).await {
+        Ok(Some(w)) => w,
+        Ok(None) => return Err(ServerFnError::new("workflow not found")),
+        Err(e) => {
+            log::error!("failed to get workflow: {:#}", e);
+            return Err(ServerFnError::new("unable to retry environment"));
+        }
+    };
+
+    let Some((idx, _)) = w
+        .failed_environment()
+        .filter(|(_, e)| e.name == environment)
+    else {
+        return Err(ServerFnError::new("environment can't be retried"));
+    };
+    let mut environments = w.environments.clone();
+    environments[idx].retry();
+
+    match client.retry(w, environments).await {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(ServerFnError::new("workflow has not failed")),
+        Err(e) => {
+            log::error!("failed to retry environment: {:#}", e);
+            Err(ServerFnError::new("unable to retry environment"))
+        }
+    }
+}
+
+/// Starts a workflow that is waiting for approval because of a `[require approval]`
+/// directive.
+#[server(ApproveWorkflow)]
+pub async fn approve_workflow(owner: String, repo: String, id: i64) -> Result<(), ServerFnError> {
+    let session = crate::auth::require_action(&owner, &repo, Action::Approve).await?;
+
+    let created_at =
+        DateTime::from_timestamp(id, 0).ok_or_else(|| ServerFnError::new("invalid workflow id"))?;
+    let client = workflow::client().await;
+    let w = match client.get(&owner, &repo, created_at).await {
+        Ok(Some(w)) => w,
+        Ok(None) => return Err(ServerFnError::new("workflow not found")),
+        Err(e) => {
+            log::error!("failed to get workflow: {:#}", e);
+            return Err(ServerFnError::new("unable to approve workflow"));
         }
     };
+    if !w.awaiting_approval() {
+        return Err(ServerFnError::new("workflow is not waiting for approval"));
+    }
+
+    let approval = workflow::Approval {
+        by: session.user.login,
+        at: Utc::now(),
+    };
+    match client.approve(w, approval.clone()).await {
+        Ok(Some(_)) => {
+            log::info!("{} approved workflow {id} of {owner}/{repo}", approval.by);
+            Ok(())
+        }
+        Ok(None) => Err(ServerFnError::new("workflow is not waiting for approval")),
+        Err(e) => {
+            log::error!("failed to approve workflow: {:#}", e);
+            Err(ServerFnError::new("unable to approve workflow"))
+        }
+    }
+}
+
+/// Makes a running workflow deploy its next environment now rather than waiting out the
+/// stability period, optionally skipping environments yet to start.
+#[server(Promote)]
+pub async fn promote(
+    owner: String,
+    repo: String,
+    id: i64,
+    reason: String,
+    skip: Vec<String>,
+) -> Result<(), ServerFnError> {
+    let session = crate::auth::require_action(&owner, &repo, Action::Promote).await?;
+
+    let reason = reason.trim().to_string();
+    if reason.is_empty() {
+        return Err(ServerFnError::new("a justification is required"));
+    }
 
-    let installations = github::list_user_installations(access_token.value())
+    let created_at =
+        DateTime::from_timestamp(id, 0).ok_or_else(|| ServerFnError::new("invalid workflow id"))?;
+    let client = workflow::client().await;
+    let w = match client.get(&owner, &repo, created_at).await {
+        Ok(Some(w)) => w,
+        Ok(None) => return Err(ServerFnError::new("workflow not found")),
+        Err(e) => {
+            log::error!("failed to get workflow: {:#}", e);
+            return Err(ServerFnError::new("unable to promote workflow"));
+        }
+    };
+    let settings = crate::settings::client()
         .await
-        .context("listing user orgs")
+        .get(&owner, &repo)
+        .await
+        .map_err(|e| {
+            log::error!("failed to get repo settings: {:#}", e);
+            ServerFnError::new("unable to promote workflow")
+        })?;
+
+    let mut protected = settings.protected_environments;
+    if let Some(config) = &w.config {
+        protected.extend(config.policies.protected_environments.iter().cloned());
+    }
+
+    let now = Utc::now();
+    let environments = w
+        .skip_environments(&skip, &protected, now)
         .map_err(ServerFnError::new)?;
+    let promotion = workflow::Override {
+        by: session.user.login,
+        reason,
+        at: now,
+        skipped: skip,
+    };
+
+    match client.promote(w, environments, promotion.clone()).await {
+        Ok(Some(_)) => {
+            log::info!(
+                "{} promoted workflow {id} of {owner}/{repo}, skipping {:?}: {}",
+                promotion.by,
+                promotion.skipped,
+                promotion.reason
+            );
+            Ok(())
+        }
+        Ok(None) => Err(ServerFnError::new("workflow is not running")),
+        Err(e) => {
+            log::error!("failed to promote workflow: {:#}", e);
+            Err(ServerFnError::new("unable to promote workflow"))
+        }
+    }
+}
 
-    let futures =
-        installations
-            .into_iter()
-            .map(|i| async move {
-                github::list_installation_repositories(access_token.value(), i.id).await
-            })
-            .collect::<Vec<_>>();
-
-    let mut repos = vec![];
-    for f in futures {
-        let x = f.await.map_err(ServerFnError::new)?;
-        repos.extend(x);
+/// Lists what the logged in user is allowed to do to the repository, so the dashboard
+/// only shows them controls they can use.
+#[server(ListAllowedActions)]
+pub async fn list_allowed_actions(
+    owner: String,
+    repo: String,
+) -> Result<Vec<Action>, ServerFnError> {
+    if owner.is_empty() || repo.is_empty() {
+        return Ok(vec![]);
     }
 
-    Ok(repos)
+    let session = crate::auth::require_repo_access(&owner, &repo).await?;
+    let role = crate::auth::repository_role(&session, &owner, &repo).await?;
+
+    Ok(role.allowed_actions())
 }
 
+#[server(GetBlock)]
+pub async fn get_block(owner: String, repo: String) -> Result<Option<Block>, ServerFnError> {
+    if owner.is_empty() || repo.is_empty() {
+        return Ok(None);
+    }
+
+    crate::auth::require_repo_access(&owner, &repo).await?;
+
+    crate::blocks::client()
+        .await
+        .get(&owner, &repo)
+        .await
+        .map_err(|e| {
+            log::error!("failed to get block: {:#}", e);
+            ServerFnError::new("unable to get deployment pause")
+        })
+}
+
+#[server(PauseDeployments)]
+pub async fn pause_deployments(
+    owner: String,
+    repo: String,
+    reason: String,
+) -> Result<Block, ServerFnError> {
+    let session = crate::auth::require_action(&owner, &repo, Action::Pause).await?;
+
+    let block = crate::blocks::client()
+        .await
+        .create(&owner, &repo, reason.trim().to_string(), session.user.login)
+        .await
+        .map_err(|e| {
+            log::error!("failed to create block: {:#}", e);
+            ServerFnError::new("unable to pause deployments")
+        })?;
+
+    log::info!("{} paused deployments to {owner}/{repo}", block.created_by);
+    Ok(block)
+}
+
+#[server(ResumeDeployments)]
+pub async fn resume_deployments(owner: String, repo: String) -> Result<(), ServerFnError> {
+    let session = crate::auth::require_action(&owner, &repo, Action::Pause).await?;
+
+    crate::blocks::client()
+        .await
+        .delete(&owner, &repo)
+        .await
+        .map_err(|e| {
+            log::error!("failed to delete block: {:#}", e);
+            ServerFnError::new("unable to resume deployments")
+        })?;
+
+    log::info!(
+        "{} resumed deployments to {owner}/{repo}",
+        session.user.login
+    );
+    Ok(())
+}
+
+/// Picks which environments to redeploy an earlier workflow's commit to.
 #[component]
-fn WorkflowCard(workflow: Workflow) -> impl IntoView {
+fn RedeployForm(workflow: Workflow, dialog: NodeRef<Dialog>) -> impl IntoView {
+    let redeploy = create_server_action::<Redeploy>();
+    let environments = workflow
+        .environments
+        .iter()
+        .map(|e| e.name.clone())
+        .collect::<Vec<_>>();
+    let (selected, set_selected) = create_signal(environments.clone());
+    let (skip_stability, set_skip_stability) = create_signal(false);
+    let target = store_value((
+        workflow.owner.clone(),
+        workflow.repo.clone(),
+        workflow.created_at.to_dt().timestamp(),
+    ));
+
+    let navigate = use_navigate();
+    create_effect(move |_| {
+        if let Some(Ok(path)) = redeploy.value().get() {
+            if let Some(dialog) = dialog.get() {
+                dialog.close();
+            }
+            navigate(&path, Default::default());
+        }
+    });
+
+    view! {
+        <h2 class="font-bold text-xl mb-2">Redeploy</h2>
+        <p class="mb-6 text-sm">
+            {format!(
+                "Creates a rollback workflow deploying {} again, to the environments picked below.",
+                workflow.sha.chars().take(7).collect::<String>(),
+            )}
+
+        </p>
+        <div class="flex flex-col gap-2">
+            {environments
+                .into_iter()
+                .map(|name| {
+                    let value = name.clone();
+                    let checked = name.clone();
+                    view! {
+                        <label class="text-sm">
+                            <input
+                                type="checkbox"
+                                class="mr-2"
+                                prop:checked=move || selected.with(|s| s.contains(&checked))
+                                on:change=move |ev| {
+                                    let checked = event_target_checked(&ev);
+                                    set_selected
+                                        .update(|s| {
+                                            s.retain(|n| *n != value);
+                                            if checked {
+                                                s.push(value.clone());
+                                            }
+                                        });
+                                }
+                            />
+
+                            {name}
+                        </label>
+                    }
+                })
+                .collect_view()}
+            <label class="text-sm mt-2">
+                <input
+                    type="checkbox"
+                    class="mr-2"
+                    prop:checked=skip_stability
+                    on:change=move |ev| set_skip_stability(event_target_checked(&ev))
+                />
+                "Skip stability periods"
+            </label>
+        </div>
+        {move || {
+            redeploy
+                .value()
+                .get()
+                .and_then(|r| r.err())
+                .map(|e| view! { <p class="mt-4 text-sm text-red-500">{e.to_string()}</p> })
+        }}
+
+        <div class="mt-8 flex gap-4">
+            <button
+                class="bg-gray-800 dark:bg-gray-900 text-white font-semibold py-2 px-4 rounded"
+                disabled=redeploy.pending()
+                on:click=move |_| {
+                    let (owner, repo, id) = target.get_value();
+                    redeploy
+                        .dispatch(Redeploy {
+                            owner,
+                            repo,
+                            id,
+                            environments: selected.get_untracked(),
+                            skip_stability: skip_stability.get_untracked(),
+                        });
+                }
+            >
+
+                Redeploy
+            </button>
+            <button
+                class="border-gray-300 hover:border-gray-400 rounded py-2 px-4 border"
+                type="button"
+                on:click=move |_| {
+                    if let Some(dialog) = dialog.get() {
+                        dialog.close();
+                    }
+                }
+            >
+
+                Close
+            </button>
+        </div>
+    }
+}
+
+/// Promotes a running workflow past its stability period, with a justification and any
+/// environments to skip.
+#[component]
+fn PromoteForm(workflow: Workflow, dialog: NodeRef<Dialog>) -> impl IntoView {
+    let promote = create_server_action::<Promote>();
+    let pending = workflow
+        .environments
+        .iter()
+        .filter(|e| e.status == EnvironmentStatus::Pending)
+        .map(|e| e.name.clone())
+        .collect::<Vec<_>>();
+    let has_pending = !pending.is_empty();
+    let (skip, set_skip) = create_signal(Vec::<String>::new());
+    let (reason, set_reason) = create_signal(String::new());
+    let target = store_value((
+        workflow.owner.clone(),
+        workflow.repo.clone(),
+        workflow.created_at.to_dt().timestamp(),
+    ));
+
+    create_effect(move |_| {
+        if let Some(Ok(())) = promote.value().get() {
+            if let Some(dialog) = dialog.get() {
+                dialog.close();
+            }
+        }
+    });
+
+    view! {
+        <h2 class="font-bold text-xl mb-2">Promote now</h2>
+        <p class="mb-6 text-sm">
+            "Starts the next environment now instead of waiting out the stability period. Who promoted it and why is recorded on the workflow."
+        </p>
+        <textarea
+            placeholder="Justification, e.g. hotfix for incident"
+            class="w-full rounded-md border border-input bg-white dark:bg-gray-900 px-3 py-2 text-sm"
+            required
+            prop:value=reason
+            on:input=move |ev| set_reason(event_target_value(&ev))
+        ></textarea>
+        <Show when=move || has_pending>
+            <p class="mt-4 mb-2 text-sm font-semibold">Skip environments</p>
+        </Show>
+        <div class="flex flex-col gap-2">
+            {pending
+                .into_iter()
+                .map(|name| {
+                    let value = name.clone();
+                    let checked = name.clone();
+                    view! {
+                        <label class="text-sm">
+                            <input
+                                type="checkbox"
+                                class="mr-2"
+                                prop:checked=move || skip.with(|s| s.contains(&checked))
+                                on:change=move |ev| {
+                                    let checked = event_target_checked(&ev);
+                                    set_skip
+                                        .update(|s| {
+                                            s.retain(|n| *n != value);
+                                            if checked {
+                                                s.push(value.clone());
+                                            }
+                                        });
+                                }
+                            />
+
+                            {name}
+                        </label>
+                    }
+                })
+                .collect_view()}
+        </div>
+        {move || {
+            promote
+                .value()
+                .get()
+                .and_then(|r| r.err())
+                .map(|e| view! { <p class="mt-4 text-sm text-red-500">{e.to_string()}</p> })
+        }}
+
+        <div class="mt-8 flex gap-4">
+            <button
+                class="bg-gray-800 dark:bg-gray-900 text-white font-semibold py-2 px-4 rounded"
+                disabled=move || promote.pending().get() || reason.with(|r| r.trim().is_empty())
+                on:click=move |_| {
+                    let (owner, repo, id) = target.get_value();
+                    promote
+                        .dispatch(Promote {
+                            owner,
+                            repo,
+                            id,
+                            reason: reason.get_untracked(),
+                            skip: skip.get_untracked(),
+                        });
+                }
+            >
+
+                Promote
+            </button>
+            <button
+                class="border-gray-300 hover:border-gray-400 rounded py-2 px-4 border"
+                type="button"
+                on:click=move |_| {
+                    if let Some(dialog) = dialog.get() {
+                        dialog.close();
+                    }
+                }
+            >
+
+                Close
+            </button>
+        </div>
+    }
+}
+
+#[component]
+fn WorkflowCard(
+    workflow: Workflow,
+    can_cancel: Signal<bool>,
+    can_redeploy: Signal<bool>,
+    can_promote: Signal<bool>,
+    can_approve: Signal<bool>,
+) -> impl IntoView {
     let local_time: DateTime<Local> = DateTime::from(workflow.created_at.to_dt());
+    let path = workflow.path();
+    let commit_message = workflow.commit_message.clone();
+    let cancel = create_server_action::<Cancel>();
+    let running = workflow.status == workflow::Status::Running;
+    let cancellable = matches!(
+        workflow.status,
+        workflow::Status::Running | workflow::Status::Paused
+    );
+    let succeeded = workflow.status == workflow::Status::Success;
+    let redeploy_dialog = create_node_ref::<Dialog>();
+    let promote_dialog = create_node_ref::<Dialog>();
+    let overrides = workflow.overrides.clone();
+    let approve = create_server_action::<ApproveWorkflow>();
+    let awaiting_approval = workflow.awaiting_approval();
+    let rollback_of = workflow.rollback_of_path();
+    let retry = create_server_action::<RetryEnvironment>();
+    let retryable = workflow.failed_environment().map(|(_, e)| e.name.clone());
+    let original = store_value(workflow.clone());
+    let target = store_value((
+        workflow.owner.clone(),
+        workflow.repo.clone(),
+        workflow.created_at.to_dt().timestamp(),
+    ));
     view! {
         <div class="rounded-lg border border-gray-300 dark:border-gray-600 bg-gray-200 dark:bg-gray-700 text-card-foreground shadow-sm">
             <div class="p-6">
-                <h2 class="text-xl font-bold mb-1">{workflow.commit_message}</h2>
+                <h2 class="text-xl font-bold mb-1">
+                    <A href=path>{commit_message.clone()}</A>
+                </h2>
                 <p class="text-sm mb-1 font-extralight">
                     Created {format!("{}", local_time.format("%d %b, %Y, %H:%M"))}
                 </p>
+                {workflow
+                    .provenance
+                    .clone()
+                    .map(|p| {
+                        view! {
+                            <p class="text-sm mb-1 font-extralight">
+                                "Triggered by " {p.actor.clone()} " on " {p.event_name.clone()} " ("
+                                <a class="underline" rel="external noopener" href=p.run_url()>
+                                    "view run"
+                                </a> ")"
+                            </p>
+                        }
+                    })}
+                {rollback_of
+                    .map(|path| {
+                        view! {
+                            <p class="text-sm mb-1 font-extralight">
+                                "Rollback of " <A class="underline" href=path>
+                                    "an earlier workflow"
+                                </A>
+                            </p>
+                        }
+                    })}
+                {(!workflow.directives.is_empty())
+                    .then(|| {
+                        view! {
+                            <p class="text-sm mb-1 font-mono">
+                                {workflow
+                                    .directives
+                                    .iter()
+                                    .map(|d| d.to_string())
+                                    .collect::<Vec<_>>()
+                                    .join(" ")}
+                            </p>
+                        }
+                    })}
+                {workflow
+                    .approval
+                    .clone()
+                    .map(|a| {
+                        view! {
+                            <p class="text-sm mb-1 font-extralight">
+                                {format!("Approved by {}", a.by)}
+                            </p>
+                        }
+                    })}
+                {workflow
+                    .required_checks
+                    .clone()
+                    .filter(|c| !c.passed())
+                    .map(|c| {
+                        view! {
+                            <p
+                                class="text-sm mb-1"
+                                class=("text-red-500", !c.failed.is_empty())
+                                class=("text-yellow-500", c.failed.is_empty())
+                            >
+                                {c.describe()}
+                            </p>
+                        }
+                    })}
+                {overrides
+                    .iter()
+                    .map(|o| {
+                        view! { <p class="text-sm mb-1 text-purple-500">{o.describe()}</p> }
+                    })
+                    .collect_view()}
+
                 <p
                     class="text-sm mb-6"
                     class=("text-green-500", move || workflow.status == workflow::Status::Success)
                     class=("text-red-500", move || workflow.status == workflow::Status::Failure)
                     class=("text-yellow-500", move || workflow.status == workflow::Status::Running)
                     class=("text-orange-500", move || workflow.status == workflow::Status::Paused)
+                    class=("text-gray-500", move || workflow.status == workflow::Status::Cancelled)
                 >
 
                     Status:
                     {format!("{}", workflow.status)}
                 </p>
+                <Show when=move || awaiting_approval && can_approve()>
+                    <button
+                        class="mb-6 mr-2 text-sm bg-gray-800 dark:bg-gray-900 text-white font-semibold rounded py-1 px-3"
+                        disabled=approve.pending()
+                        on:click=move |_| {
+                            let (owner, repo, id) = target.get_value();
+                            approve.dispatch(ApproveWorkflow { owner, repo, id });
+                        }
+                    >
+
+                        Approve
+                    </button>
+                </Show>
+                <Show when=move || cancellable && can_cancel()>
+                    <button
+                        class="mb-6 text-sm border-gray-300 hover:border-gray-400 rounded py-1 px-3 border"
+                        disabled=cancel.pending()
+                        on:click=move |_| {
+                            let confirmed = window()
+                                .confirm_with_message("Cancel this workflow?")
+                                .unwrap_or(false);
+                            if confirmed {
+                                let (owner, repo, id) = target.get_value();
+                                cancel.dispatch(Cancel { owner, repo, id });
+                            }
+                        }
+                    >
+
+                        Cancel
+                    </button>
+                </Show>
+                <Show when=move || succeeded && can_redeploy()>
+                    <button
+                        class="mb-6 text-sm border-gray-300 hover:border-gray-400 rounded py-1 px-3 border"
+                        on:click=move |_| {
+                            _ = redeploy_dialog.get().unwrap().show_modal();
+                        }
+                    >
+
+                        Redeploy
+                    </button>
+                    <dialog
+                        _ref=redeploy_dialog
+                        class="p-8 w-full max-w-lg drop-shadow-lg dark:bg-gray-700 rounded-xl dark:text-white"
+                    >
+                        <RedeployForm workflow=original.get_value() dialog=redeploy_dialog/>
+                    </dialog>
+                </Show>
+                <Show when=move || running && can_promote()>
+                    <button
+                        class="mb-6 text-sm border-gray-300 hover:border-gray-400 rounded py-1 px-3 border"
+                        on:click=move |_| {
+                            _ = promote_dialog.get().unwrap().show_modal();
+                        }
+                    >
+
+                        Promote now
+                    </button>
+                    <dialog
+                        _ref=promote_dialog
+                        class="p-8 w-full max-w-lg drop-shadow-lg dark:bg-gray-700 rounded-xl dark:text-white"
+                    >
+                        <PromoteForm workflow=original.get_value() dialog=promote_dialog/>
+                    </dialog>
+                </Show>
+                {move || {
+                    cancel
+                        .value()
+                        .get()
+                        .and_then(|r| r.err())
+                        .or_else(|| retry.value().get().and_then(|r| r.err()))
+                        .or_else(|| approve.value().get().and_then(|r| r.err()))
+                        .map(|e| view! { <p class="mb-6 text-sm text-red-500">{e.to_string()}</p> })
+                }}
+
                 <div class="flex flex-wrap justify-start gap-2">
                     <For
                         each=move || workflow.environments.clone().into_iter()
@@ -100,47 +783,81 @@ fn WorkflowCard(workflow: Workflow) -> impl IntoView {
                             let owner = workflow.owner.clone();
                             let repo = workflow.repo.clone();
                             let name = w.name.clone();
+                            let retryable = retryable.as_deref() == Some(w.name.as_str());
+                            let environment = store_value(w.name.clone());
                             view! {
-                                <a
-                                    rel="external noopener"
-                                    href=move || {
-                                        format!(
-                                            "https://github.com/{}/{}/deployments/{}",
-                                            owner,
-                                            repo,
-                                            name,
+                                <span class="inline-flex items-center gap-1">
+                                    <a
+                                        rel="external noopener"
+                                        href=move || {
+                                            format!(
+                                                "https://github.com/{}/{}/deployments/{}",
+                                                owner,
+                                                repo,
+                                                name,
+                                            )
+                                        }
+
+                                        class="px-2 py-1 text-white rounded"
+                                        class=(
+                                            "bg-green-500",
+                                            move || w.status == EnvironmentStatus::Success,
                                         )
-                                    }
 
-                                    class="px-2 py-1 text-white rounded"
-                                    class=(
-                                        "bg-green-500",
-                                        move || w.status == EnvironmentStatus::Success,
-                                    )
-
-                                    class=(
-                                        "bg-green-500",
-                                        move || w.status == EnvironmentStatus::Queued,
-                                    )
-
-                                    class=(
-                                        "bg-red-500",
-                                        move || w.status == EnvironmentStatus::Failure,
-                                    )
-
-                                    class=(
-                                        "bg-yellow-500",
-                                        move || w.status == EnvironmentStatus::Running,
-                                    )
-
-                                    class=(
-                                        "bg-gray-500",
-                                        move || w.status == EnvironmentStatus::Pending,
-                                    )
-                                >
+                                        class=(
+                                            "bg-green-500",
+                                            move || w.status == EnvironmentStatus::Queued,
+                                        )
+
+                                        class=(
+                                            "bg-red-500",
+                                            move || w.status == EnvironmentStatus::Failure,
+                                        )
+
+                                        class=(
+                                            "bg-yellow-500",
+                                            move || w.status == EnvironmentStatus::Running,
+                                        )
 
-                                    {w.name}
-                                </a>
+                                        class=(
+                                            "bg-gray-500",
+                                            move || w.status == EnvironmentStatus::Pending,
+                                        )
+
+                                        class=(
+                                            "bg-gray-400",
+                                            move || w.status == EnvironmentStatus::Cancelled,
+                                        )
+
+                                        class=(
+                                            "bg-purple-500",
+                                            move || w.status == EnvironmentStatus::Skipped,
+                                        )
+                                    >
+
+                                        {w.name}
+                                    </a>
+                                    <Show when=move || retryable && can_redeploy()>
+                                        <button
+                                            class="px-2 py-1 text-sm border-gray-300 hover:border-gray-400 rounded border"
+                                            title="Retry this environment"
+                                            disabled=retry.pending()
+                                            on:click=move |_| {
+                                                let (owner, repo, id) = target.get_value();
+                                                retry
+                                                    .dispatch(RetryEnvironment {
+                                                        owner,
+                                                        repo,
+                                                        id,
+                                                        environment: environment.get_value(),
+                                                    });
+                                            }
+                                        >
+
+                                            "Retry"
+                                        </button>
+                                    </Show>
+                                </span>
                             }
                         }
                     />
@@ -151,18 +868,203 @@ fn WorkflowCard(workflow: Workflow) -> impl IntoView {
     }
 }
 
+/// Parses the value of a date input as the given local time on that day.
+fn parse_date(value: &str, time: NaiveTime) -> Option<DateTime<Utc>> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()?
+        .and_time(time)
+        .and_local_timezone(Local)
+        .earliest()
+        .map(|d| d.with_timezone(&Utc))
+}
+
 #[component]
-fn Deployments(repo: ReadSignal<String>) -> impl IntoView {
-    let workflows = create_resource(repo, |repo| {
-        let parts = repo.split('/').collect::<Vec<_>>();
-        let owner = parts.first().unwrap_or(&"").to_string();
-        let repo = parts.get(1).unwrap_or(&"").to_string();
-        list_workflows(owner, repo)
+fn Filters(
+    filter: ReadSignal<WorkflowFilter>,
+    set_filter: WriteSignal<WorkflowFilter>,
+) -> impl IntoView {
+    let input_class = "rounded-md border border-input bg-white dark:bg-gray-900 px-3 py-2 text-sm";
+
+    view! {
+        <div class="px-6 pt-8 flex flex-wrap gap-2">
+            <select
+                class=input_class
+                on:change=move |ev| {
+                    let value = event_target_value(&ev);
+                    let status = Status::all().into_iter().find(|s| s.to_string() == value);
+                    set_filter.update(|f| f.status = status);
+                }
+            >
+
+                <option value="" selected=move || filter().status.is_none()>
+                    "All statuses"
+                </option>
+                <For
+                    each=Status::all
+                    key=|s| s.to_string()
+                    children=move |s: Status| {
+                        view! {
+                            <option value=s.to_string() selected=move || filter().status == Some(s)>
+                                {s.to_string()}
+                            </option>
+                        }
+                    }
+                />
+
+            </select>
+            <input
+                type="text"
+                placeholder="Branch or ref"
+                class=input_class
+                prop:value=move || filter().git_ref.unwrap_or_default()
+                on:change=move |ev| {
+                    let value = event_target_value(&ev);
+                    set_filter.update(|f| f.git_ref = Some(value).filter(|v| !v.is_empty()));
+                }
+            />
+
+            <input
+                type="date"
+                title="Created from"
+                class=input_class
+                on:change=move |ev| {
+                    let from = parse_date(&event_target_value(&ev), NaiveTime::MIN);
+                    set_filter.update(|f| f.from = from);
+                }
+            />
+
+            <input
+                type="date"
+                title="Created until"
+                class=input_class
+                on:change=move |ev| {
+                    let to = NaiveTime::from_hms_opt(23, 59, 59)
+                        .and_then(|t| parse_date(&event_target_value(&ev), t));
+                    set_filter.update(|f| f.to = to);
+                }
+            />
+
+            <input
+                type="search"
+                placeholder="Search commit messages"
+                class=format!("{input_class} flex-grow")
+                prop:value=move || filter().search.unwrap_or_default()
+                on:change=move |ev| {
+                    let value = event_target_value(&ev);
+                    set_filter.update(|f| f.search = Some(value).filter(|v| !v.is_empty()));
+                }
+            />
+
+        </div>
+    }
+}
+
+#[component]
+fn Deployments(
+    repo: ReadSignal<String>,
+    matrix: ReadSignal<bool>,
+    #[prop(into)] can_cancel: Signal<bool>,
+    #[prop(into)] can_redeploy: Signal<bool>,
+    #[prop(into)] can_promote: Signal<bool>,
+    #[prop(into)] can_approve: Signal<bool>,
+) -> impl IntoView {
+    let (filter, set_filter) = create_signal(WorkflowFilter::default());
+
+    // The first page is kept up to date, while older pages are loaded as the user scrolls.
+    let first_page = create_resource(
+        move || (repo.get(), filter.get()),
+        |(repo, filter)| {
+            let (owner, repo) = split_repo(&repo);
+            list_workflows(owner, repo, filter, None)
+        },
+    );
+    let (older, set_older) = create_signal(Vec::<Workflow>::new());
+    // The cursor after the last older page, or `None` if none have been loaded.
+    let (older_cursor, set_older_cursor) = create_signal(None::<Option<String>>);
+
+    create_effect(move |_| {
+        repo.track();
+        filter.track();
+        set_older(vec![]);
+        set_older_cursor(None);
+    });
+
+    let next_cursor = move || match older_cursor.get() {
+        Some(cursor) => cursor,
+        None => first_page.get().and_then(|p| p.ok()).and_then(|p| p.cursor),
+    };
+
+    let load_more = create_action(move |cursor: &String| {
+        let (owner, repo) = split_repo(&repo.get_untracked());
+        list_workflows(owner, repo, filter.get_untracked(), Some(cursor.clone()))
     });
+    create_effect(move |_| {
+        if let Some(Ok(page)) = load_more.value().get() {
+            set_older.update(|older| older.extend(page.workflows));
+            set_older_cursor(Some(page.cursor));
+        }
+    });
+    let fetch_more = move || {
+        if load_more.pending().get_untracked() {
+            return;
+        }
+        if let Some(cursor) = next_cursor() {
+            load_more.dispatch(cursor);
+        }
+    };
+
+    let scroll = window_event_listener(ev::scroll, move |_| {
+        let window = window();
+        let scrolled = window.scroll_y().unwrap_or_default()
+            + window
+                .inner_height()
+                .ok()
+                .and_then(|h| h.as_f64())
+                .unwrap_or_default();
+        let height = document()
+            .body()
+            .map(|b| b.scroll_height() as f64)
+            .unwrap_or_default();
+        if scrolled >= height - 400.0 {
+            fetch_more();
+        }
+    });
+    on_cleanup(move || scroll.remove());
+
+    let streaming = use_workflow_events(
+        repo.into(),
+        move |w: Workflow| {
+            if older.with_untracked(|o| o.iter().any(|x| x.created_at == w.created_at)) {
+                set_older.update(|older| {
+                    if let Some(x) = older.iter_mut().find(|x| x.created_at == w.created_at) {
+                        *x = w;
+                    }
+                });
+                return;
+            }
+
+            first_page.update(|page| {
+                if let Some(Ok(page)) = page {
+                    match page
+                        .workflows
+                        .iter()
+                        .position(|x| x.created_at == w.created_at)
+                    {
+                        Some(i) => page.workflows[i] = w,
+                        None if filter.get_untracked().matches(&w) => page.workflows.insert(0, w),
+                        None => {}
+                    }
+                }
+            });
+        },
+        move || first_page.refetch(),
+    );
     create_effect(move |_| {
         let handle = set_interval_with_handle(
             move || {
-                workflows.refetch();
+                if !streaming.get_untracked() {
+                    first_page.refetch();
+                }
             },
             Duration::from_secs(5),
         )
@@ -173,20 +1075,50 @@ fn Deployments(repo: ReadSignal<String>) -> impl IntoView {
         })
     });
 
+    let workflows = move || {
+        let mut workflows = first_page
+            .get()
+            .and_then(|p| p.ok())
+            .map(|p| p.workflows)
+            .unwrap_or_default();
+        for w in older.get() {
+            if !workflows.iter().any(|x| x.created_at == w.created_at) {
+                workflows.push(w);
+            }
+        }
+        workflows
+    };
+
     view! {
+        <Filters filter=filter set_filter=set_filter/>
         <Transition fallback=move || view! { <p>"Loading..."</p> }>
             <main class="px-6 py-8 grid grid-cols-1 gap-4 gap-y-8">
                 {move || {
-                    workflows
+                    first_page
                         .get()
-                        .map(|w| match w {
-                            Ok(w) => {
+                        .map(|p| match p {
+                            Ok(p) if p.workflows.is_empty() => {
+                                view! { <p>No workflows found.</p> }.into_view()
+                            }
+                            Ok(_) if matrix.get() => {
+                                view! { <Matrix repo=repo workflows=Signal::derive(workflows)/> }
+                                    .into_view()
+                            }
+                            Ok(_) => {
                                 view! {
                                     <For
-                                        each=move || w.clone()
-                                        key=|w| w.id.clone()
+                                        each=workflows
+                                        key=|w| (w.created_at.to_dt(), w.updated_at)
                                         children=move |w: Workflow| {
-                                            view! { <WorkflowCard workflow=w/> }
+                                            view! {
+                                                <WorkflowCard
+                                                    workflow=w
+                                                    can_cancel=can_cancel
+                                                    can_redeploy=can_redeploy
+                                                    can_promote=can_promote
+                                                    can_approve=can_approve
+                                                />
+                                            }
                                         }
                                     />
                                 }
@@ -199,111 +1131,264 @@ fn Deployments(repo: ReadSignal<String>) -> impl IntoView {
                         })
                 }}
 
+                <Show when=move || next_cursor().is_some()>
+                    <button
+                        class="mx-auto border-gray-300 hover:border-gray-400 rounded py-2 px-4 border"
+                        on:click=move |_| fetch_more()
+                        disabled=load_more.pending()
+                    >
+                        {move || if load_more.pending().get() { "Loading..." } else { "Load more" }}
+                    </button>
+                </Show>
             </main>
         </Transition>
     }
 }
 
-#[component]
-pub fn SelectOption(is: String, value: ReadSignal<String>) -> impl IntoView {
-    let v = is.clone();
-    view! {
-        <option value=&v selected=move || value() == is>
-            {v}
-        </option>
-    }
-}
-
 #[component]
 pub fn Dashboard() -> impl IntoView {
-    let (repo, set_repo) = create_signal("".to_string());
-    let repos = create_local_resource(move || (), |_| list_repos());
-    let logout = create_server_action::<Logout>();
-
+    // The repository comes from the URL, so a selection survives a refresh and can be shared.
+    let params = use_params_map();
+    let repo_param = move || {
+        params.with(|p| match (p.get("owner"), p.get("repo")) {
+            (Some(owner), Some(repo)) => format!("{owner}/{repo}"),
+            _ => String::new(),
+        })
+    };
+    let (repo, set_repo) = create_signal(repo_param());
     create_effect(move |_| {
-        if repo.get().is_empty() {
-            set_repo(
-                repos
-                    .get()
-                    .and_then(|r| r.ok())
-                    .and_then(|r| r.first().cloned())
-                    .unwrap_or_default(),
-            );
+        let selected = repo_param();
+        if selected != repo.get_untracked() {
+            set_repo(selected);
         }
     });
+    let navigate = use_navigate();
+    let select_repo = move |r: String| {
+        // Replace rather than push when picking the default repository, so going back
+        // doesn't land on `/dashboard` only to be sent forward again.
+        let replace = repo.get_untracked().is_empty();
+        navigate(
+            &format!("/dashboard/{r}"),
+            NavigateOptions {
+                replace,
+                ..Default::default()
+            },
+        );
+    };
+
+    let allowed_actions = create_resource(repo, |repo| {
+        let (owner, repo) = split_repo(&repo);
+        list_allowed_actions(owner, repo)
+    });
+    let can = move |action: Action| {
+        allowed_actions
+            .get()
+            .and_then(|a| a.ok())
+            .map(|a| a.contains(&action))
+            .unwrap_or(false)
+    };
+
+    let pause = create_server_action::<PauseDeployments>();
+    let resume = create_server_action::<ResumeDeployments>();
+    let block = create_resource(
+        move || (repo.get(), pause.version().get(), resume.version().get()),
+        |(repo, _, _)| {
+            let (owner, repo) = split_repo(&repo);
+            get_block(owner, repo)
+        },
+    );
+    let current_block = move || block.get().and_then(|b| b.ok()).flatten();
+    let (reason, set_reason) = create_signal(String::new());
+    let (matrix, set_matrix) = create_signal(false);
 
     let dialog = create_node_ref::<Dialog>();
+    let tokens_dialog = create_node_ref::<Dialog>();
+    let roles_dialog = create_node_ref::<Dialog>();
+    let settings_dialog = create_node_ref::<Dialog>();
 
     view! {
         <div class="min-h-screen bg-gray-100 dark:bg-gray-800 dark:text-white">
-            <header class="p-6 bg-white shadow dark:bg-gray-900">
-                <div class="flex justify-between max-w-6xl mx-auto w-full">
-                    <nav class="flex flex-row gap-8">
-                        <A href="/dashboard" class="flex items-center">
-                            Deployments
-                        </A>
-                        <A href="/environments" class="flex items-center">
-                            Environments
-                        </A>
-                    </nav>
-                    <ActionForm class="flex items-center gap-4" action=logout>
-                        <button type="submit" class="text-sm">
-                            Logout
-                        </button>
-                    </ActionForm>
-                </div>
-            </header>
+            <Header/>
             <main class="max-w-4xl mx-auto w-full">
                 <div class="pt-12 px-6 flex flex-col sm:flex-row sm:justify-between">
-                    <Transition fallback=move || {
-                        view! {
-                            <select class="min-w-24 max-w-48 flex h-10 w-full items-center justify-between rounded-md border border-input bg-white dark:bg-gray-900 px-3 py-2 text-sm focus:outline-none focus:ring-2 focus:ring-ring focus:ring-offset-2 disabled:cursor-not-allowed disabled:opacity-50">
-                                "Loading..."
-                            </select>
-                        }
-                    }>
-
-                        {move || {
-                            repos
-                                .get()
-                                .map(|repos| match repos {
-                                    Ok(repos) => {
-                                        view! {
-                                            <select
-                                                class="flex h-10 items-center justify-between rounded-md border border-input bg-white dark:bg-gray-900 px-3 py-2 text-sm ring-offset-background placeholder:text-muted-foreground focus:outline-none focus:ring-2 focus:ring-ring focus:ring-offset-2 disabled:cursor-not-allowed disabled:opacity-50"
-                                                on:change=move |ev| {
-                                                    let new_value = event_target_value(&ev);
-                                                    set_repo(new_value);
-                                                }
-                                            >
-
-                                                <For each=move || repos.clone() key=|r| r.clone() let:child>
-                                                    <SelectOption is=child value=repo/>
-                                                </For>
-                                            </select>
-                                        }
-                                            .into_view()
+                    <RepoSelect repo=repo on_change=select_repo/>
+                    <div class="flex flex-col sm:flex-row gap-4">
+                        <Show when=move || can(Action::ManageSettings)>
+                            <button
+                                on:click=move |_| {
+                                    _ = settings_dialog.get().unwrap().show_modal();
+                                }
+
+                                class="mt-6 sm:mt-0 border-gray-300 hover:border-gray-400 rounded py-2 px-4 border"
+                            >
+                                Settings
+                            </button>
+                        </Show>
+                        <Show when=move || can(Action::ManageRoles)>
+                            <button
+                                on:click=move |_| {
+                                    _ = roles_dialog.get().unwrap().show_modal();
+                                }
+
+                                class="mt-6 sm:mt-0 border-gray-300 hover:border-gray-400 rounded py-2 px-4 border"
+                            >
+                                Roles
+                            </button>
+                        </Show>
+                        <Show when=move || can(Action::ManageTokens)>
+                            <button
+                                on:click=move |_| {
+                                    _ = tokens_dialog.get().unwrap().show_modal();
+                                }
+
+                                class="mt-6 sm:mt-0 border-gray-300 hover:border-gray-400 rounded py-2 px-4 border"
+                            >
+                                API Tokens
+                            </button>
+                        </Show>
+                        <Show when=move || can(Action::Pause)>
+                            <Show
+                                when=move || current_block().is_some()
+                                fallback=move || {
+                                    view! {
+                                        <button
+                                            on:click=move |_| {
+                                                _ = dialog.get().unwrap().show_modal();
+                                            }
+
+                                            class="bg-rose-800 text-white font-semibold py-2 px-4 rounded hover:bg-rose-700 transition duration-300"
+                                        >
+                                            Pause Deployments
+                                        </button>
                                     }
-                                    Err(e) => {
-                                        view! { <p>Something went wrong: {format!("{e}")}</p> }
-                                            .into_view()
+                                }
+                            >
+
+                                <button
+                                    on:click=move |_| {
+                                        let (owner, repo) = split_repo(&repo.get_untracked());
+                                        resume.dispatch(ResumeDeployments { owner, repo });
                                     }
-                                })
-                        }}
 
-                    </Transition>
+                                    class="bg-gray-800 dark:bg-gray-900 text-white font-semibold py-2 px-4 rounded"
+                                >
+                                    Resume Deployments
+                                </button>
+                            </Show>
+                        </Show>
+                    </div>
+                    <Title text=repo/>
+                </div>
+                {move || {
+                    current_block()
+                        .map(|b| {
+                            let created_at: DateTime<Local> = DateTime::from(b.created_at);
+                            let reason = b.reason.clone();
+                            let has_reason = !reason.is_empty();
+                            view! {
+                                <div class="mx-6 mt-8 p-4 rounded-lg bg-orange-100 dark:bg-orange-900 text-sm">
+                                    <p class="font-semibold">
+                                        {format!(
+                                            "Deployments paused by {} on {}",
+                                            b.created_by,
+                                            created_at.format("%d %b, %Y, %H:%M"),
+                                        )}
+
+                                    </p>
+                                    <Show when=move || has_reason>
+                                        <p>{reason.clone()}</p>
+                                    </Show>
+                                </div>
+                            }
+                        })
+                }}
+
+                <div class="px-6 pt-8 flex justify-end text-sm">
+                    <button
+                        class="rounded-l py-1 px-3 border border-gray-300"
+                        class=("bg-gray-300 dark:bg-gray-600", move || !matrix())
+                        on:click=move |_| set_matrix(false)
+                    >
+                        Cards
+                    </button>
+                    <button
+                        class="rounded-r py-1 px-3 border border-l-0 border-gray-300"
+                        class=("bg-gray-300 dark:bg-gray-600", matrix)
+                        on:click=move |_| set_matrix(true)
+                    >
+                        Matrix
+                    </button>
+                </div>
+                <Deployments
+                    repo=repo
+                    matrix=matrix
+                    can_cancel=Signal::derive(move || can(Action::Cancel))
+                    can_redeploy=Signal::derive(move || can(Action::Redeploy))
+                    can_promote=Signal::derive(move || can(Action::Promote))
+                    can_approve=Signal::derive(move || can(Action::Approve))
+                />
+                <dialog
+                    _ref=settings_dialog
+                    class="p-8 w-full max-w-2xl drop-shadow-lg dark:bg-gray-700 rounded-xl dark:text-white"
+                >
+                    <h2 class="font-bold text-xl mb-8">Settings</h2>
+                    <div class="flex flex-col gap-8">
+                        <ProtectedEnvironments repo=repo/>
+                        <Directives repo=repo/>
+                        <BranchRules repo=repo/>
+                        <RequiredChecks repo=repo/>
+                    </div>
                     <button
+                        class="mt-8 border-gray-300 hover:border-gray-400 rounded py-2 px-4 border"
+                        type="button"
                         on:click=move |_| {
-                            _ = dialog.get().unwrap().show_modal();
+                            settings_dialog.get().unwrap().close();
                         }
+                    >
 
-                        class="bg-rose-800 mt-6 sm:mt-0 text-white font-semibold py-2 px-4 rounded hover:bg-rose-700 transition duration-300"
+                        Close
+                    </button>
+                </dialog>
+                <dialog
+                    _ref=roles_dialog
+                    class="p-8 w-full max-w-2xl drop-shadow-lg dark:bg-gray-700 rounded-xl dark:text-white"
+                >
+                    <h2 class="font-bold text-xl mb-2">Roles</h2>
+                    <p class="mb-8 text-sm">
+                        "Roles normally come from GitHub repository permissions. A role granted here raises it within pipedream only."
+                    </p>
+                    <RoleGrants repo=repo/>
+                    <button
+                        class="mt-8 border-gray-300 hover:border-gray-400 rounded py-2 px-4 border"
+                        type="button"
+                        on:click=move |_| {
+                            roles_dialog.get().unwrap().close();
+                        }
                     >
-                        Pause Deployments
+
+                        Close
                     </button>
-                    <Title text=repo/>
-                </div>
-                <Deployments repo=repo/>
+                </dialog>
+                <dialog
+                    _ref=tokens_dialog
+                    class="p-8 w-full max-w-2xl drop-shadow-lg dark:bg-gray-700 rounded-xl dark:text-white"
+                >
+                    <h2 class="font-bold text-xl mb-2">API Tokens</h2>
+                    <p class="mb-8 text-sm">
+                        Tokens let CI systems other than GitHub Actions create workflows, by passing them in the authorization header.
+                    </p>
+                    <ApiTokens repo=repo/>
+                    <button
+                        class="mt-8 border-gray-300 hover:border-gray-400 rounded py-2 px-4 border"
+                        type="button"
+                        on:click=move |_| {
+                            tokens_dialog.get().unwrap().close();
+                        }
+                    >
+
+                        Close
+                    </button>
+                </dialog>
                 <dialog
                     _ref=dialog
                     class="p-8 drop-shadow-lg dark:bg-gray-700 rounded-xl dark:text-white"
@@ -313,17 +1398,36 @@ pub fn Dashboard() -> impl IntoView {
                     <p>
                         This will stop new deployments, as well as those ongoing. You must manually re-enable deployments.
                     </p>
+                    <input
+                        type="text"
+                        placeholder="Reason, e.g. incident in progress"
+                        class="mt-6 w-full rounded-md border border-input bg-white dark:bg-gray-900 px-3 py-2 text-sm"
+                        prop:value=reason
+                        on:input=move |ev| set_reason(event_target_value(&ev))
+                    />
                     <div class="flex flex-col-reverse sm:flex-row justify-between mt-12">
                         <button
                             class="mt-4 sm:mt-0 border-gray-300 hover:border-gray-400 rounded py-2 px-4 border"
                             type="button"
+                            on:click=move |_| {
+                                dialog.get().unwrap().close();
+                            }
                         >
+
                             Cancel
                         </button>
                         <button
                             type="submit"
                             on:click=move |_| {
-                                _ = dialog.get().unwrap().show_modal();
+                                let (owner, repo) = split_repo(&repo.get_untracked());
+                                pause
+                                    .dispatch(PauseDeployments {
+                                        owner,
+                                        repo,
+                                        reason: reason.get_untracked(),
+                                    });
+                                set_reason(String::new());
+                                dialog.get().unwrap().close();
                             }
 
                             class="bg-rose-800 text-white font-semibold py-2 px-4 rounded hover:bg-rose-700 transition duration-300"
@@ -0,0 +1,177 @@
+use super::components::{Header, RepoSelect};
+use super::split_repo;
+use crate::environments::LiveEnvironment;
+use chrono::{DateTime, Local};
+use leptos::*;
+use leptos_meta::Title;
+use leptos_router::A;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EnvironmentRow {
+    pub live: LiveEnvironment,
+    /// How many workflows behind the nearest environment upstream this one is, if any.
+    pub behind: Option<usize>,
+}
+
+#[server(ListEnvironments)]
+pub async fn list_environments(
+    owner: String,
+    repo: String,
+) -> Result<Vec<EnvironmentRow>, ServerFnError> {
+    use crate::{environments, workflow};
+
+    if owner.is_empty() || repo.is_empty() {
+        return Ok(vec![]);
+    }
+
+    crate::auth::require_repo_access(&owner, &repo).await?;
+
+    let live = environments::client()
+        .await
+        .list(&owner, &repo)
+        .await
+        .map_err(|e| {
+            log::error!("failed to list environments: {:#}", e);
+            ServerFnError::new("unable to list environments")
+        })?;
+
+    let mut rows = vec![];
+    for (i, environment) in live.iter().enumerate() {
+        let behind = match i.checked_sub(1).and_then(|i| live.get(i)) {
+            None => None,
+            Some(upstream)
+                if upstream.workflow_created_at.to_dt()
+                    <= environment.workflow_created_at.to_dt() =>
+            {
+                Some(0)
+            }
+            Some(upstream) => Some(
+                workflow::client()
+                    .await
+                    .count_between(
+                        &owner,
+                        &repo,
+                        &environment.workflow_created_at,
+                        &upstream.workflow_created_at,
+                    )
+                    .await
+                    .map_err(|e| {
+                        log::error!("failed to count workflows: {:#}", e);
+                        ServerFnError::new("unable to list environments")
+                    })?,
+            ),
+        };
+
+        rows.push(EnvironmentRow {
+            live: environment.clone(),
+            behind,
+        });
+    }
+
+    Ok(rows)
+}
+
+#[component]
+fn Row(row: EnvironmentRow) -> impl IntoView {
+    let live = row.live;
+    let live_at: DateTime<Local> = DateTime::from(live.live_at);
+    let created_at: DateTime<Local> = DateTime::from(live.workflow_created_at.to_dt());
+    let behind = match row.behind {
+        None => "-".to_string(),
+        Some(0) => "up to date".to_string(),
+        Some(1) => "1 workflow".to_string(),
+        Some(n) => format!("{n} workflows"),
+    };
+    let short_sha = live.sha.chars().take(7).collect::<String>();
+
+    view! {
+        <tr class="border-t border-gray-300 dark:border-gray-600 align-top">
+            <td class="py-3 pr-4 font-semibold">
+                <A class="underline" href=format!("/environments/{}/{}", live.id, live.name)>
+                    {live.name.clone()}
+                </A>
+            </td>
+            <td class="py-3 pr-4">
+                <a
+                    class="font-mono underline"
+                    rel="external noopener"
+                    href=format!("https://github.com/{}/commit/{}", live.id, live.sha)
+                >
+                    {short_sha}
+                </a>
+                <p class="text-sm">{live.commit_message.clone()}</p>
+            </td>
+            <td class="py-3 pr-4 text-sm">
+                {format!("{} · {}", live.git_ref, created_at.format("%d %b, %Y, %H:%M"))}
+            </td>
+            <td class="py-3 pr-4 text-sm">{format!("{}", live_at.format("%d %b, %Y, %H:%M"))}</td>
+            <td class="py-3 text-sm">{behind}</td>
+        </tr>
+    }
+}
+
+#[component]
+pub fn Environments() -> impl IntoView {
+    let (repo, set_repo) = create_signal("".to_string());
+    let environments = create_resource(repo, |repo| {
+        let (owner, repo) = split_repo(&repo);
+        list_environments(owner, repo)
+    });
+
+    view! {
+        <div class="min-h-screen bg-gray-100 dark:bg-gray-800 dark:text-white">
+            <Header/>
+            <main class="max-w-4xl mx-auto w-full">
+                <div class="pt-12 px-6">
+                    <RepoSelect repo=repo on_change=move |r| set_repo(r)/>
+                    <Title text=repo/>
+                </div>
+                <Transition fallback=move || view! { <p>"Loading..."</p> }>
+                    <div class="px-6 py-8 overflow-x-auto">
+                        {move || {
+                            environments
+                                .get()
+                                .map(|e| match e {
+                                    Ok(e) if e.is_empty() => {
+                                        view! { <p>Nothing has been deployed yet.</p> }.into_view()
+                                    }
+                                    Ok(e) => {
+                                        view! {
+                                            <table class="w-full text-left">
+                                                <thead>
+                                                    <tr class="text-sm">
+                                                        <th class="pb-2 pr-4">Environment</th>
+                                                        <th class="pb-2 pr-4">Commit</th>
+                                                        <th class="pb-2 pr-4">Workflow</th>
+                                                        <th class="pb-2 pr-4">Live since</th>
+                                                        <th class="pb-2">Behind upstream</th>
+                                                    </tr>
+                                                </thead>
+                                                <tbody>
+                                                    <For
+                                                        each=move || e.clone()
+                                                        key=|r| r.live.name.clone()
+                                                        children=move |r: EnvironmentRow| {
+                                                            view! { <Row row=r/> }
+                                                        }
+                                                    />
+
+                                                </tbody>
+                                            </table>
+                                        }
+                                            .into_view()
+                                    }
+                                    Err(e) => {
+                                        view! { <p>Something went wrong: {format!("{e}")}</p> }
+                                            .into_view()
+                                    }
+                                })
+                        }}
+
+                    </div>
+                </Transition>
+            </main>
+        </div>
+    }
+}
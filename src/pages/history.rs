@@ -0,0 +1,258 @@
+use super::components::Header;
+use super::format_duration;
+use crate::workflow::{Deployment, DeploymentPage, EnvironmentStatus};
+use chrono::{DateTime, Local, NaiveDateTime, Utc};
+use leptos::*;
+use leptos_meta::Title;
+use leptos_router::{use_params_map, A};
+
+#[server(ListDeployments)]
+pub async fn list_deployments(
+    owner: String,
+    repo: String,
+    environment: String,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    cursor: Option<String>,
+) -> Result<DeploymentPage, ServerFnError> {
+    use crate::workflow;
+
+    crate::auth::require_repo_access(&owner, &repo).await?;
+
+    match workflow::client()
+        .await
+        .list_deployments(&owner, &repo, &environment, from, to, cursor)
+        .await
+    {
+        Err(e) => {
+            log::error!("failed to list deployments: {:#}", e);
+            Err(ServerFnError::new("unable to list deployments"))
+        }
+        Ok(v) => Ok(v),
+    }
+}
+
+/// Parses the value of a datetime-local input.
+fn parse_datetime(value: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M")
+        .ok()?
+        .and_local_timezone(Local)
+        .earliest()
+        .map(|d| d.with_timezone(&Utc))
+}
+
+#[component]
+fn Row(deployment: Deployment) -> impl IntoView {
+    let started_at: DateTime<Local> = DateTime::from(deployment.started_at.to_dt());
+    let finished_at = deployment
+        .finished_at
+        .map(|f| {
+            DateTime::<Local>::from(f)
+                .format("%d %b, %Y, %H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_else(|| "-".to_string());
+    let duration = format_duration(deployment.duration(Utc::now()));
+    let short_sha = deployment.sha.chars().take(7).collect::<String>();
+    let status = deployment.status;
+
+    view! {
+        <tr class="border-t border-gray-300 dark:border-gray-600 align-top">
+            <td class="py-3 pr-4 text-sm">
+                {format!("{}", started_at.format("%d %b, %Y, %H:%M:%S"))}
+            </td>
+            <td class="py-3 pr-4 text-sm">{finished_at}</td>
+            <td class="py-3 pr-4 text-sm">{duration}</td>
+            <td class="py-3 pr-4">
+                <a
+                    class="font-mono underline"
+                    rel="external noopener"
+                    href=format!(
+                        "https://github.com/{}/{}/commit/{}",
+                        deployment.owner,
+                        deployment.repo,
+                        deployment.sha,
+                    )
+                >
+
+                    {short_sha}
+                </a>
+                <p class="text-sm">
+                    <A href=deployment.workflow_path()>{deployment.commit_message.clone()}</A>
+                </p>
+            </td>
+            <td
+                class="py-3 text-sm font-semibold"
+                class=("text-green-500", move || status == EnvironmentStatus::Success)
+                class=("text-red-500", move || status == EnvironmentStatus::Failure)
+                class=("text-gray-500", move || status == EnvironmentStatus::Cancelled)
+                class=("text-purple-500", move || status == EnvironmentStatus::Skipped)
+                class=("text-yellow-500", move || !status.is_terminal())
+            >
+                {format!("{:?}", status)}
+            </td>
+        </tr>
+    }
+}
+
+/// Every deployment to one environment of a repository, across all workflows.
+#[component]
+pub fn DeploymentHistory() -> impl IntoView {
+    let params = use_params_map();
+    let param = move |name: &str| params.with(|p| p.get(name).cloned().unwrap_or_default());
+
+    let (from, set_from) = create_signal(None::<DateTime<Utc>>);
+    let (to, set_to) = create_signal(None::<DateTime<Utc>>);
+
+    let first_page = create_resource(
+        move || {
+            (
+                param("owner"),
+                param("repo"),
+                param("environment"),
+                from.get(),
+                to.get(),
+            )
+        },
+        |(owner, repo, environment, from, to)| {
+            list_deployments(owner, repo, environment, from, to, None)
+        },
+    );
+    let (older, set_older) = create_signal(Vec::<Deployment>::new());
+    // The cursor after the last older page, or `None` if none have been loaded.
+    let (older_cursor, set_older_cursor) = create_signal(None::<Option<String>>);
+    create_effect(move |_| {
+        params.track();
+        from.track();
+        to.track();
+        set_older(vec![]);
+        set_older_cursor(None);
+    });
+
+    let next_cursor = move || match older_cursor.get() {
+        Some(cursor) => cursor,
+        None => first_page.get().and_then(|p| p.ok()).and_then(|p| p.cursor),
+    };
+    let load_more = create_action(move |cursor: &String| {
+        list_deployments(
+            param("owner"),
+            param("repo"),
+            param("environment"),
+            from.get_untracked(),
+            to.get_untracked(),
+            Some(cursor.clone()),
+        )
+    });
+    create_effect(move |_| {
+        if let Some(Ok(page)) = load_more.value().get() {
+            set_older.update(|older| older.extend(page.deployments));
+            set_older_cursor(Some(page.cursor));
+        }
+    });
+
+    let deployments = move || {
+        let mut deployments = first_page
+            .get()
+            .and_then(|p| p.ok())
+            .map(|p| p.deployments)
+            .unwrap_or_default();
+        deployments.extend(older.get());
+        deployments
+    };
+    let title = move || {
+        format!(
+            "{}/{} {}",
+            param("owner"),
+            param("repo"),
+            param("environment")
+        )
+    };
+    let input_class = "rounded-md border border-input bg-white dark:bg-gray-900 px-3 py-2 text-sm";
+
+    view! {
+        <div class="min-h-screen bg-gray-100 dark:bg-gray-800 dark:text-white">
+            <Header/>
+            <main class="max-w-4xl mx-auto w-full">
+                <div class="pt-12 px-6">
+                    <h1 class="text-2xl font-bold">{title}</h1>
+                    <Title text=title/>
+                    <div class="mt-4 flex flex-wrap items-center gap-2 text-sm">
+                        "Started between"
+                        <input
+                            type="datetime-local"
+                            class=input_class
+                            on:change=move |ev| set_from(parse_datetime(&event_target_value(&ev)))
+                        />
+                        "and"
+                        <input
+                            type="datetime-local"
+                            class=input_class
+                            on:change=move |ev| set_to(parse_datetime(&event_target_value(&ev)))
+                        />
+                    </div>
+                </div>
+                <Transition fallback=move || view! { <p>"Loading..."</p> }>
+                    <div class="px-6 py-8 overflow-x-auto">
+                        {move || {
+                            first_page
+                                .get()
+                                .map(|p| match p {
+                                    Ok(p) if p.deployments.is_empty() => {
+                                        view! { <p>No deployments found.</p> }.into_view()
+                                    }
+                                    Ok(_) => {
+                                        view! {
+                                            <table class="w-full text-left">
+                                                <thead>
+                                                    <tr class="text-sm">
+                                                        <th class="pb-2 pr-4">Started</th>
+                                                        <th class="pb-2 pr-4">Finished</th>
+                                                        <th class="pb-2 pr-4">Duration</th>
+                                                        <th class="pb-2 pr-4">Commit</th>
+                                                        <th class="pb-2">Outcome</th>
+                                                    </tr>
+                                                </thead>
+                                                <tbody>
+                                                    <For
+                                                        each=deployments
+                                                        key=|d| (d.started_at.to_dt(), d.finished_at)
+                                                        children=move |d: Deployment| {
+                                                            view! { <Row deployment=d/> }
+                                                        }
+                                                    />
+
+                                                </tbody>
+                                            </table>
+                                        }
+                                            .into_view()
+                                    }
+                                    Err(e) => {
+                                        view! { <p>Something went wrong: {format!("{e}")}</p> }
+                                            .into_view()
+                                    }
+                                })
+                        }}
+
+                        <Show when=move || next_cursor().is_some()>
+                            <button
+                                class="mt-6 border-gray-300 hover:border-gray-400 rounded py-2 px-4 border"
+                                on:click=move |_| {
+                                    if let Some(cursor) = next_cursor() {
+                                        load_more.dispatch(cursor);
+                                    }
+                                }
+
+                                disabled=load_more.pending()
+                            >
+                                {move || {
+                                    if load_more.pending().get() { "Loading..." } else { "Load more" }
+                                }}
+
+                            </button>
+                        </Show>
+                    </div>
+                </Transition>
+            </main>
+        </div>
+    }
+}
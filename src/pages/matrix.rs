@@ -0,0 +1,166 @@
+use super::environments::list_environments;
+use super::{format_duration, split_repo};
+use crate::workflow::{EnvironmentStatus, Status, Workflow};
+use chrono::{DateTime, Local, Utc};
+use leptos::*;
+use leptos_router::A;
+use std::collections::HashMap;
+
+/// The repository's environments in pipeline order, as they appear across the workflows.
+fn environment_names(workflows: &[Workflow]) -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+    for w in workflows {
+        for env in &w.environments {
+            if !names.contains(&env.name) {
+                names.push(env.name.clone());
+            }
+        }
+    }
+    names
+}
+
+#[component]
+fn Cell(workflow: Workflow, name: String, live: bool, now: DateTime<Utc>) -> impl IntoView {
+    let Some(env) = workflow
+        .environments
+        .iter()
+        .find(|e| e.name == name)
+        .cloned()
+    else {
+        return view! { <td class="p-1"></td> }.into_view();
+    };
+    let status = env.status;
+    let duration = env.duration(now).map(format_duration);
+
+    view! {
+        <td class="p-1">
+            <a
+                href=workflow.path()
+                class="block rounded px-2 py-1 text-xs text-white whitespace-nowrap"
+                class=("bg-green-500", move || status == EnvironmentStatus::Success)
+                class=("bg-green-500", move || status == EnvironmentStatus::Queued)
+                class=("bg-red-500", move || status == EnvironmentStatus::Failure)
+                class=("bg-yellow-500", move || status == EnvironmentStatus::Running)
+                class=("bg-gray-500", move || status == EnvironmentStatus::Pending)
+                class=("bg-gray-400", move || status == EnvironmentStatus::Cancelled)
+                class=("bg-purple-500", move || status == EnvironmentStatus::Skipped)
+                class=("ring-2 ring-offset-1 ring-blue-500", move || live)
+            >
+                <span class="font-semibold">{format!("{:?}", status)}</span>
+                {duration.map(|d| format!(" · {d}"))}
+                <Show when=move || live>
+                    <span class="ml-1 uppercase font-bold">"live"</span>
+                </Show>
+            </a>
+        </td>
+    }
+    .into_view()
+}
+
+#[component]
+fn MatrixRow(
+    workflow: Workflow,
+    names: Vec<String>,
+    live: HashMap<String, DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> impl IntoView {
+    let created_at = workflow.created_at.to_dt();
+    let local_time: DateTime<Local> = DateTime::from(created_at);
+    let stuck = workflow.is_stuck(now);
+    let failed = workflow.status == Status::Failure;
+    let short_sha = workflow.sha.chars().take(7).collect::<String>();
+    let path = workflow.path();
+    let commit_message = workflow.commit_message.clone();
+
+    view! {
+        <tr
+            class="border-t border-gray-300 dark:border-gray-600 align-top"
+            class=("bg-orange-100 dark:bg-orange-900", move || stuck)
+        >
+            <td class="py-2 pr-4 max-w-xs">
+                <A href=path class="font-semibold truncate block">
+                    {commit_message}
+                </A>
+                <p class="text-xs font-extralight">
+                    <span class="font-mono">{short_sha}</span>
+                    {format!(" · {}", local_time.format("%d %b, %H:%M"))}
+                    <Show when=move || stuck>
+                        <span class="ml-1 font-bold text-orange-500">"stuck"</span>
+                    </Show>
+                    <Show when=move || failed>
+                        <span class="ml-1 font-bold text-red-500">"failed"</span>
+                    </Show>
+                </p>
+            </td>
+            {names
+                .into_iter()
+                .map(|name| {
+                    let is_live = live.get(&name) == Some(&created_at);
+                    view! { <Cell workflow=workflow.clone() name=name live=is_live now=now/> }
+                })
+                .collect_view()}
+        </tr>
+    }
+}
+
+/// Shows recent workflows as a grid of commits against environments, highlighting what
+/// is live in each environment and which commits have stopped making progress.
+#[component]
+pub(crate) fn Matrix(
+    repo: ReadSignal<String>,
+    #[prop(into)] workflows: Signal<Vec<Workflow>>,
+) -> impl IntoView {
+    let environments = create_resource(repo, |repo| {
+        let (owner, repo) = split_repo(&repo);
+        list_environments(owner, repo)
+    });
+    let live = move || {
+        environments
+            .get()
+            .and_then(|e| e.ok())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|row| (row.live.name, row.live.workflow_created_at.to_dt()))
+            .collect::<HashMap<_, _>>()
+    };
+
+    view! {
+        <div class="overflow-x-auto">
+            {move || {
+                let workflows = workflows.get();
+                let names = environment_names(&workflows);
+                let live = live();
+                let now = Utc::now();
+                view! {
+                    <table class="w-full text-left text-sm">
+                        <thead>
+                            <tr>
+                                <th class="pb-2 pr-4">Commit</th>
+                                {names
+                                    .iter()
+                                    .map(|name| view! { <th class="pb-2 px-1">{name.clone()}</th> })
+                                    .collect_view()}
+                            </tr>
+                        </thead>
+                        <tbody>
+                            {workflows
+                                .into_iter()
+                                .map(|w| {
+                                    view! {
+                                        <MatrixRow
+                                            workflow=w
+                                            names=names.clone()
+                                            live=live.clone()
+                                            now=now
+                                        />
+                                    }
+                                })
+                                .collect_view()}
+                        </tbody>
+                    </table>
+                }
+            }}
+
+        </div>
+    }
+}
@@ -1,6 +1,28 @@
 mod components;
 mod dashboard;
+mod environments;
+mod history;
 mod home;
+mod matrix;
+mod overview;
+mod roles;
+mod settings;
+mod tokens;
+mod workflow;
 
 pub use dashboard::*;
+pub use environments::*;
+pub use history::*;
 pub use home::*;
+pub use overview::*;
+pub use workflow::*;
+
+use crate::workflow::format_duration;
+
+/// Splits an `owner/repo` string into its owner and repo.
+fn split_repo(repo: &str) -> (String, String) {
+    let parts = repo.split('/').collect::<Vec<_>>();
+    let owner = parts.first().unwrap_or(&"").to_string();
+    let repo = parts.get(1).unwrap_or(&"").to_string();
+    (owner, repo)
+}
@@ -0,0 +1,227 @@
+use super::components::Header;
+use crate::blocks::Block;
+use crate::workflow::{EnvironmentStatus, Status, Workflow};
+use chrono::{DateTime, Local};
+use leptos::*;
+use leptos_meta::Title;
+use leptos_router::A;
+use serde::{Deserialize, Serialize};
+
+/// Where one repository's deployments are at, for the org overview.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RepoOverview {
+    /// Composite of owner/repo.
+    pub repo: String,
+    pub latest: Option<Workflow>,
+    /// Environments currently being deployed to, across all running workflows.
+    pub running: Vec<String>,
+    pub block: Option<Block>,
+    /// How many workflows failed in the last 24 hours.
+    pub recent_failures: usize,
+}
+
+#[cfg(feature = "ssr")]
+async fn overview(
+    owner: String,
+    repo: String,
+    running: &[Workflow],
+) -> Result<RepoOverview, anyhow::Error> {
+    use crate::blocks;
+    use crate::workflow::{self, WorkflowFilter};
+    use chrono::Utc;
+
+    let client = workflow::client().await;
+    let latest = client.list(&owner, &repo, &WorkflowFilter::default(), None, 1);
+    let recent_failures = client.count(
+        &owner,
+        &repo,
+        &WorkflowFilter {
+            status: Some(Status::Failure),
+            from: Some(Utc::now() - chrono::Duration::hours(24)),
+            ..Default::default()
+        },
+    );
+    let block = async { blocks::client().await.get(&owner, &repo).await };
+
+    let (latest, recent_failures, block) = futures::try_join!(latest, recent_failures, block)?;
+
+    let running = running
+        .iter()
+        .filter(|w| w.owner == owner && w.repo == repo)
+        .flat_map(|w| &w.environments)
+        .filter(|e| {
+            matches!(
+                e.status,
+                EnvironmentStatus::Running | EnvironmentStatus::Queued
+            )
+        })
+        .map(|e| e.name.clone())
+        .collect();
+
+    Ok(RepoOverview {
+        repo: format!("{owner}/{repo}"),
+        latest: latest.workflows.into_iter().next(),
+        running,
+        block,
+        recent_failures,
+    })
+}
+
+/// Summarises every repository the user can see, aggregated server side so the page
+/// doesn't have to make a round trip per repository.
+#[server(GetOverview)]
+pub async fn get_overview() -> Result<Vec<RepoOverview>, ServerFnError> {
+    use futures::{StreamExt, TryStreamExt};
+
+    let session = crate::auth::require_user()?;
+    let repos = crate::auth::user_repositories(&session).await?;
+
+    let running = crate::workflow::client()
+        .await
+        .list_running()
+        .await
+        .map_err(|e| {
+            log::error!("failed to list running workflows: {:#}", e);
+            ServerFnError::new("unable to get overview")
+        })?;
+
+    futures::stream::iter(repos)
+        .map(|r| {
+            let (owner, repo) = super::split_repo(&r);
+            overview(owner, repo, &running)
+        })
+        .buffered(8)
+        .try_collect()
+        .await
+        .map_err(|e| {
+            log::error!("failed to get overview: {:#}", e);
+            ServerFnError::new("unable to get overview")
+        })
+}
+
+#[component]
+fn Row(overview: RepoOverview) -> impl IntoView {
+    let latest = overview
+        .latest
+        .map(|w| {
+            let status = w.status;
+            let created_at: DateTime<Local> = DateTime::from(w.created_at.to_dt());
+            view! {
+                <A href=w.path() class="block underline">
+                    {w.commit_message.clone()}
+                </A>
+                <p
+                    class="text-sm"
+                    class=("text-green-500", move || status == Status::Success)
+                    class=("text-red-500", move || status == Status::Failure)
+                    class=("text-yellow-500", move || status == Status::Running)
+                    class=("text-orange-500", move || status == Status::Paused)
+                    class=("text-gray-500", move || status == Status::Cancelled)
+                >
+                    {format!("{} · {}", status, created_at.format("%d %b, %H:%M"))}
+                </p>
+            }
+            .into_view()
+        })
+        .unwrap_or_else(|| "-".into_view());
+    let running = overview.running;
+    let has_failures = overview.recent_failures > 0;
+
+    view! {
+        <tr class="border-t border-gray-300 dark:border-gray-600 align-top">
+            <td class="py-3 pr-4 font-semibold">
+                <A href=format!("/dashboard/{}", overview.repo) class="underline">
+                    {overview.repo.clone()}
+                </A>
+            </td>
+            <td class="py-3 pr-4">{latest}</td>
+            <td class="py-3 pr-4">
+                <div class="flex flex-wrap gap-1">
+                    {running
+                        .into_iter()
+                        .map(|name| {
+                            view! {
+                                <span class="px-2 py-1 text-xs text-white rounded bg-yellow-500">
+                                    {name}
+                                </span>
+                            }
+                        })
+                        .collect_view()}
+                </div>
+            </td>
+            <td class="py-3 pr-4 text-sm">
+                {overview
+                    .block
+                    .map(|b| {
+                        view! {
+                            <span class="font-semibold text-orange-500">"Paused"</span>
+                            <p>{format!("by {}", b.created_by)}</p>
+                            <p>{b.reason.clone()}</p>
+                        }
+                    })}
+
+            </td>
+            <td class="py-3 text-sm" class=("text-red-500 font-semibold", move || has_failures)>
+                {overview.recent_failures}
+            </td>
+        </tr>
+    }
+}
+
+#[component]
+pub fn Overview() -> impl IntoView {
+    let overview = create_resource(|| (), |_| get_overview());
+
+    view! {
+        <div class="min-h-screen bg-gray-100 dark:bg-gray-800 dark:text-white">
+            <Header/>
+            <Title text="Overview"/>
+            <main class="max-w-6xl mx-auto w-full">
+                <Transition fallback=move || view! { <p>"Loading..."</p> }>
+                    <div class="px-6 py-8 overflow-x-auto">
+                        {move || {
+                            overview
+                                .get()
+                                .map(|o| match o {
+                                    Ok(o) if o.is_empty() => {
+                                        view! { <p>No repositories found.</p> }.into_view()
+                                    }
+                                    Ok(o) => {
+                                        view! {
+                                            <table class="w-full text-left">
+                                                <thead>
+                                                    <tr class="text-sm">
+                                                        <th class="pb-2 pr-4">Repository</th>
+                                                        <th class="pb-2 pr-4">Latest workflow</th>
+                                                        <th class="pb-2 pr-4">Deploying</th>
+                                                        <th class="pb-2 pr-4">Blocks</th>
+                                                        <th class="pb-2">Failures (24h)</th>
+                                                    </tr>
+                                                </thead>
+                                                <tbody>
+                                                    <For
+                                                        each=move || o.clone()
+                                                        key=|o| o.repo.clone()
+                                                        children=move |o: RepoOverview| {
+                                                            view! { <Row overview=o/> }
+                                                        }
+                                                    />
+
+                                                </tbody>
+                                            </table>
+                                        }
+                                            .into_view()
+                                    }
+                                    Err(e) => {
+                                        view! { <p>Something went wrong: {format!("{e}")}</p> }
+                                            .into_view()
+                                    }
+                                })
+                        }}
+
+                    </div>
+                </Transition>
+            </main>
+        </div>
+    }
+}
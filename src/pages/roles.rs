@@ -0,0 +1,134 @@
+use super::split_repo;
+use crate::auth::Role;
+use crate::settings::{list_role_grants, UpdateRoleGrant};
+use leptos::*;
+
+#[component]
+pub fn RoleGrants(repo: ReadSignal<String>) -> impl IntoView {
+    let update = create_server_action::<UpdateRoleGrant>();
+
+    let grants = create_resource(
+        move || (repo.get(), update.version().get()),
+        |(repo, _)| {
+            let (owner, repo) = split_repo(&repo);
+            list_role_grants(owner, repo)
+        },
+    );
+
+    let (login, set_login) = create_signal(String::new());
+    let (role, set_role) = create_signal(Role::Write);
+
+    view! {
+        <div class="flex flex-col gap-6">
+            <Transition fallback=move || view! { <p>"Loading..."</p> }>
+                {move || {
+                    grants
+                        .get()
+                        .map(|g| match g {
+                            Ok(g) if g.is_empty() => view! { <p>No roles granted yet.</p> }.into_view(),
+                            Ok(g) => {
+                                view! {
+                                    <ul class="divide-y divide-gray-300 dark:divide-gray-600">
+                                        <For
+                                            each=move || g.clone()
+                                            key=|(login, role)| format!("{login}:{role}")
+                                            children=move |(login, role): (String, Role)| {
+                                                let l = login.clone();
+                                                view! {
+                                                    <li class="flex justify-between items-center gap-4 py-2">
+                                                        <p>
+                                                            <span class="font-semibold">{login}</span>
+                                                            {format!(" · {role}")}
+                                                        </p>
+                                                        <button
+                                                            class="text-sm border-gray-300 hover:border-gray-400 rounded py-1 px-2 border"
+                                                            on:click=move |_| {
+                                                                let (owner, repo) = split_repo(&repo.get_untracked());
+                                                                update
+                                                                    .dispatch(UpdateRoleGrant {
+                                                                        owner,
+                                                                        repo,
+                                                                        login: l.clone(),
+                                                                        role: None,
+                                                                    });
+                                                            }
+                                                        >
+
+                                                            Remove
+                                                        </button>
+                                                    </li>
+                                                }
+                                            }
+                                        />
+
+                                    </ul>
+                                }
+                                    .into_view()
+                            }
+                            Err(e) => {
+                                view! { <p>Something went wrong: {format!("{e}")}</p> }.into_view()
+                            }
+                        })
+                }}
+
+            </Transition>
+            <div class="flex flex-col sm:flex-row gap-2">
+                <input
+                    type="text"
+                    placeholder="GitHub login"
+                    class="flex-grow rounded-md border border-input bg-white dark:bg-gray-900 px-3 py-2 text-sm"
+                    prop:value=login
+                    on:input=move |ev| set_login(event_target_value(&ev))
+                />
+                <select
+                    class="rounded-md border border-input bg-white dark:bg-gray-900 px-3 py-2 text-sm"
+                    on:change=move |ev| {
+                        let value = event_target_value(&ev);
+                        if let Some(r) = Role::all().into_iter().find(|r| r.to_string() == value) {
+                            set_role(r);
+                        }
+                    }
+                >
+
+                    <For
+                        each=Role::all
+                        key=|r| r.to_string()
+                        children=move |r: Role| {
+                            view! {
+                                <option value=r.to_string() selected=move || role() == r>
+                                    {r.to_string()}
+                                </option>
+                            }
+                        }
+                    />
+
+                </select>
+                <button
+                    class="bg-gray-800 dark:bg-gray-900 text-white font-semibold py-2 px-4 rounded"
+                    on:click=move |_| {
+                        let (owner, repo) = split_repo(&repo.get_untracked());
+                        update
+                            .dispatch(UpdateRoleGrant {
+                                owner,
+                                repo,
+                                login: login.get_untracked(),
+                                role: Some(role.get_untracked()),
+                            });
+                        set_login(String::new());
+                    }
+                >
+
+                    Grant
+                </button>
+            </div>
+            {move || {
+                update
+                    .value()
+                    .get()
+                    .and_then(|v| v.err())
+                    .map(|e| view! { <p class="text-sm text-red-500">{format!("{e}")}</p> })
+            }}
+
+        </div>
+    }
+}
@@ -0,0 +1,361 @@
+use super::split_repo;
+use crate::settings::{
+    list_branch_rules, list_ignored_directives, list_protected_environments,
+    list_required_contexts, BranchRule, UpdateBranchRules, UpdateIgnoredDirectives,
+    UpdateProtectedEnvironments, UpdateRequiredContexts,
+};
+use crate::workflow::DirectiveKind;
+use leptos::*;
+
+/// Edits the environments that can never be skipped when promoting a workflow.
+#[component]
+pub fn ProtectedEnvironments(repo: ReadSignal<String>) -> impl IntoView {
+    let update = create_server_action::<UpdateProtectedEnvironments>();
+
+    let protected = create_resource(
+        move || (repo.get(), update.version().get()),
+        |(repo, _)| {
+            let (owner, repo) = split_repo(&repo);
+            list_protected_environments(owner, repo)
+        },
+    );
+
+    let (environments, set_environments) = create_signal(String::new());
+    create_effect(move |_| {
+        if let Some(Ok(p)) = protected.get() {
+            set_environments(p.join(", "));
+        }
+    });
+
+    view! {
+        <div class="flex flex-col gap-2">
+            <h3 class="font-semibold">Protected environments</h3>
+            <p class="text-sm">
+                "Comma separated. These environments can't be skipped when promoting a workflow."
+            </p>
+            <div class="flex flex-col sm:flex-row gap-2">
+                <input
+                    type="text"
+                    placeholder="e.g. production"
+                    class="flex-grow rounded-md border border-input bg-white dark:bg-gray-900 px-3 py-2 text-sm"
+                    prop:value=environments
+                    on:input=move |ev| set_environments(event_target_value(&ev))
+                />
+                <button
+                    class="bg-gray-800 dark:bg-gray-900 text-white font-semibold py-2 px-4 rounded"
+                    disabled=update.pending()
+                    on:click=move |_| {
+                        let (owner, repo) = split_repo(&repo.get_untracked());
+                        update
+                            .dispatch(UpdateProtectedEnvironments {
+                                owner,
+                                repo,
+                                environments: environments
+                                    .get_untracked()
+                                    .split(',')
+                                    .map(|e| e.to_string())
+                                    .collect(),
+                            });
+                    }
+                >
+
+                    Save
+                </button>
+            </div>
+            {move || {
+                update
+                    .value()
+                    .get()
+                    .and_then(|v| v.err())
+                    .or_else(|| protected.get().and_then(|p| p.err()))
+                    .map(|e| view! { <p class="text-sm text-red-500">{format!("{e}")}</p> })
+            }}
+
+        </div>
+    }
+}
+
+/// Chooses which commit message and label directives the repository honours.
+#[component]
+pub fn Directives(repo: ReadSignal<String>) -> impl IntoView {
+    let update = create_server_action::<UpdateIgnoredDirectives>();
+
+    let ignored = create_resource(
+        move || (repo.get(), update.version().get()),
+        |(repo, _)| {
+            let (owner, repo) = split_repo(&repo);
+            list_ignored_directives(owner, repo)
+        },
+    );
+    let is_ignored = move |d: DirectiveKind| {
+        ignored
+            .get()
+            .and_then(|i| i.ok())
+            .map(|i| i.contains(&d))
+            .unwrap_or(false)
+    };
+
+    view! {
+        <div class="flex flex-col gap-2">
+            <h3 class="font-semibold">Directives</h3>
+            <p class="text-sm">
+                "Commit messages and pull request labels can change a workflow with directives like "
+                <span class="font-mono">"[hotfix]"</span>
+                ". Only the directives ticked here are honoured."
+            </p>
+            <For
+                each=DirectiveKind::all
+                key=|d| d.to_string()
+                children=move |d: DirectiveKind| {
+                    view! {
+                        <label class="text-sm">
+                            <input
+                                type="checkbox"
+                                class="mr-2"
+                                prop:checked=move || !is_ignored(d)
+                                disabled=update.pending()
+                                on:change=move |ev| {
+                                    let honoured = event_target_checked(&ev);
+                                    let mut directives = ignored
+                                        .get_untracked()
+                                        .and_then(|i| i.ok())
+                                        .unwrap_or_default();
+                                    directives.retain(|x| *x != d);
+                                    if !honoured {
+                                        directives.push(d);
+                                    }
+                                    let (owner, repo) = split_repo(&repo.get_untracked());
+                                    update
+                                        .dispatch(UpdateIgnoredDirectives {
+                                            owner,
+                                            repo,
+                                            directives,
+                                        });
+                                }
+                            />
+
+                            <span class="font-mono">{format!("[{d}]")}</span>
+                        </label>
+                    }
+                }
+            />
+
+            {move || {
+                update
+                    .value()
+                    .get()
+                    .and_then(|v| v.err())
+                    .or_else(|| ignored.get().and_then(|i| i.err()))
+                    .map(|e| view! { <p class="text-sm text-red-500">{format!("{e}")}</p> })
+            }}
+
+        </div>
+    }
+}
+
+/// Edits which environments each branch may deploy to.
+#[component]
+pub fn BranchRules(repo: ReadSignal<String>) -> impl IntoView {
+    let update = create_server_action::<UpdateBranchRules>();
+
+    let rules = create_resource(
+        move || (repo.get(), update.version().get()),
+        |(repo, _)| {
+            let (owner, repo) = split_repo(&repo);
+            list_branch_rules(owner, repo)
+        },
+    );
+    let current = move || {
+        rules
+            .get_untracked()
+            .and_then(|r| r.ok())
+            .unwrap_or_default()
+    };
+    let save = move |rules: Vec<BranchRule>| {
+        let (owner, repo) = split_repo(&repo.get_untracked());
+        update.dispatch(UpdateBranchRules { owner, repo, rules });
+    };
+
+    let (pattern, set_pattern) = create_signal(String::new());
+    let (environments, set_environments) = create_signal(String::new());
+    let input_class = "rounded-md border border-input bg-white dark:bg-gray-900 px-3 py-2 text-sm";
+
+    view! {
+        <div class="flex flex-col gap-2">
+            <h3 class="font-semibold">Branch rules</h3>
+            <p class="text-sm">
+                "Which environments each branch may deploy to, where "
+                <span class="font-mono">"*"</span>
+                " matches anything. The most specific matching pattern applies, and workflows for branches no pattern matches are rejected. With no rules, any branch may deploy anywhere."
+            </p>
+            <Transition fallback=move || view! { <p>"Loading..."</p> }>
+                {move || {
+                    rules
+                        .get()
+                        .map(|r| match r {
+                            Ok(r) if r.is_empty() => {
+                                view! { <p class="text-sm">No branch rules yet.</p> }.into_view()
+                            }
+                            Ok(r) => {
+                                view! {
+                                    <ul class="divide-y divide-gray-300 dark:divide-gray-600">
+                                        {r
+                                            .into_iter()
+                                            .enumerate()
+                                            .map(|(idx, rule)| {
+                                                view! {
+                                                    <li class="flex justify-between items-center gap-4 py-2 text-sm">
+                                                        <p>
+                                                            <span class="font-mono font-semibold">
+                                                                {rule.pattern}
+                                                            </span>
+                                                            {format!(" → {}", rule.environments.join(" → "))}
+                                                        </p>
+                                                        <button
+                                                            class="border-gray-300 hover:border-gray-400 rounded py-1 px-2 border"
+                                                            on:click=move |_| {
+                                                                let mut rules = current();
+                                                                if idx < rules.len() {
+                                                                    rules.remove(idx);
+                                                                }
+                                                                save(rules);
+                                                            }
+                                                        >
+
+                                                            Remove
+                                                        </button>
+                                                    </li>
+                                                }
+                                            })
+                                            .collect_view()}
+                                    </ul>
+                                }
+                                    .into_view()
+                            }
+                            Err(e) => {
+                                view! { <p class="text-sm">Something went wrong: {format!("{e}")}</p> }
+                                    .into_view()
+                            }
+                        })
+                }}
+
+            </Transition>
+            <div class="flex flex-col sm:flex-row gap-2">
+                <input
+                    type="text"
+                    placeholder="Branch, e.g. release/*"
+                    class=input_class
+                    prop:value=pattern
+                    on:input=move |ev| set_pattern(event_target_value(&ev))
+                />
+                <input
+                    type="text"
+                    placeholder="Environments, e.g. staging, production"
+                    class=format!("{input_class} flex-grow")
+                    prop:value=environments
+                    on:input=move |ev| set_environments(event_target_value(&ev))
+                />
+                <button
+                    class="bg-gray-800 dark:bg-gray-900 text-white font-semibold py-2 px-4 rounded"
+                    disabled=update.pending()
+                    on:click=move |_| {
+                        let pattern = pattern.get_untracked();
+                        let mut rules = current();
+                        rules.retain(|r| r.pattern != pattern.trim());
+                        rules
+                            .push(BranchRule {
+                                pattern,
+                                environments: environments
+                                    .get_untracked()
+                                    .split(',')
+                                    .map(|e| e.to_string())
+                                    .collect(),
+                            });
+                        save(rules);
+                        set_pattern(String::new());
+                        set_environments(String::new());
+                    }
+                >
+
+                    Add
+                </button>
+            </div>
+            {move || {
+                update
+                    .value()
+                    .get()
+                    .and_then(|v| v.err())
+                    .map(|e| view! { <p class="text-sm text-red-500">{format!("{e}")}</p> })
+            }}
+
+        </div>
+    }
+}
+
+/// Edits the commit statuses and check runs a workflow waits on before deploying.
+#[component]
+pub fn RequiredChecks(repo: ReadSignal<String>) -> impl IntoView {
+    let update = create_server_action::<UpdateRequiredContexts>();
+
+    let required = create_resource(
+        move || (repo.get(), update.version().get()),
+        |(repo, _)| {
+            let (owner, repo) = split_repo(&repo);
+            list_required_contexts(owner, repo)
+        },
+    );
+
+    let (contexts, set_contexts) = create_signal(String::new());
+    create_effect(move |_| {
+        if let Some(Ok(r)) = required.get() {
+            set_contexts(r.join(", "));
+        }
+    });
+
+    view! {
+        <div class="flex flex-col gap-2">
+            <h3 class="font-semibold">Required checks</h3>
+            <p class="text-sm">
+                "Comma separated commit statuses or check runs, by name. Workflows wait for these to pass before deploying, and fail if any of them fail."
+            </p>
+            <div class="flex flex-col sm:flex-row gap-2">
+                <input
+                    type="text"
+                    placeholder="e.g. test, lint"
+                    class="flex-grow rounded-md border border-input bg-white dark:bg-gray-900 px-3 py-2 text-sm"
+                    prop:value=contexts
+                    on:input=move |ev| set_contexts(event_target_value(&ev))
+                />
+                <button
+                    class="bg-gray-800 dark:bg-gray-900 text-white font-semibold py-2 px-4 rounded"
+                    disabled=update.pending()
+                    on:click=move |_| {
+                        let (owner, repo) = split_repo(&repo.get_untracked());
+                        update
+                            .dispatch(UpdateRequiredContexts {
+                                owner,
+                                repo,
+                                contexts: contexts
+                                    .get_untracked()
+                                    .split(',')
+                                    .map(|c| c.to_string())
+                                    .collect(),
+                            });
+                    }
+                >
+
+                    Save
+                </button>
+            </div>
+            {move || {
+                update
+                    .value()
+                    .get()
+                    .and_then(|v| v.err())
+                    .or_else(|| required.get().and_then(|r| r.err()))
+                    .map(|e| view! { <p class="text-sm text-red-500">{format!("{e}")}</p> })
+            }}
+
+        </div>
+    }
+}
@@ -0,0 +1,329 @@
+use super::split_repo;
+use crate::tokens::{ApiToken, Operation};
+use chrono::{DateTime, Local};
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreatedToken {
+    pub secret: String,
+    pub token: ApiToken,
+}
+
+#[server(ListTokens)]
+pub async fn list_tokens(owner: String, repo: String) -> Result<Vec<ApiToken>, ServerFnError> {
+    use crate::tokens;
+
+    if owner.is_empty() || repo.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let session =
+        crate::auth::require_action(&owner, &repo, crate::auth::Action::ManageTokens).await?;
+
+    // Tokens for the whole owner are only shown to those who could have created them.
+    let mut scopes = vec![format!("{owner}/{repo}")];
+    if crate::auth::is_owner_admin(&session, &owner).await? {
+        scopes.push(owner);
+    }
+
+    let client = tokens::client().await;
+    let mut v = vec![];
+    for scope in scopes {
+        let scoped = client.list(&scope).await.map_err(|e| {
+            log::error!("failed to list tokens: {:#}", e);
+            ServerFnError::new("unable to list tokens")
+        })?;
+        v.extend(scoped);
+    }
+
+    Ok(v)
+}
+
+#[server(CreateToken)]
+pub async fn create_token(
+    owner: String,
+    repo: String,
+    name: String,
+    org_wide: bool,
+    operations: Vec<Operation>,
+    expires_in_days: i64,
+) -> Result<CreatedToken, ServerFnError> {
+    use crate::tokens;
+
+    if name.trim().is_empty() {
+        return Err(ServerFnError::new("a token name is required"));
+    }
+    if operations.is_empty() {
+        return Err(ServerFnError::new(
+            "a token must allow at least one operation",
+        ));
+    }
+    if !(1..=365).contains(&expires_in_days) {
+        return Err(ServerFnError::new(
+            "tokens must expire within 1 and 365 days",
+        ));
+    }
+
+    crate::auth::require_action(&owner, &repo, crate::auth::Action::ManageTokens).await?;
+    // A token for the whole owner reaches repositories beyond this one.
+    if org_wide {
+        crate::auth::require_owner_admin(&owner).await?;
+    }
+
+    let scope = if org_wide {
+        owner
+    } else {
+        format!("{owner}/{repo}")
+    };
+
+    let (secret, token) = tokens::client()
+        .await
+        .create(tokens::CreateTokenRequest {
+            scope,
+            name: name.trim().to_string(),
+            operations,
+            expires_in_days,
+        })
+        .await
+        .map_err(|e| {
+            log::error!("failed to create token: {:#}", e);
+            ServerFnError::new("unable to create token")
+        })?;
+
+    Ok(CreatedToken { secret, token })
+}
+
+#[server(RevokeToken)]
+pub async fn revoke_token(
+    owner: String,
+    repo: String,
+    scope: String,
+    id: String,
+) -> Result<(), ServerFnError> {
+    use crate::tokens;
+
+    if scope != owner && scope != format!("{owner}/{repo}") {
+        return Err(ServerFnError::new(
+            "token does not belong to this repository",
+        ));
+    }
+
+    crate::auth::require_action(&owner, &repo, crate::auth::Action::ManageTokens).await?;
+    if scope == owner {
+        crate::auth::require_owner_admin(&owner).await?;
+    }
+
+    tokens::client()
+        .await
+        .revoke(&scope, &id)
+        .await
+        .map_err(|e| {
+            log::error!("failed to revoke token: {:#}", e);
+            ServerFnError::new("unable to revoke token")
+        })
+}
+
+#[component]
+fn TokenRow(token: ApiToken, repo: ReadSignal<String>) -> impl IntoView {
+    let revoke = expect_context::<Action<RevokeToken, Result<(), ServerFnError>>>();
+    let expires_at: DateTime<Local> = DateTime::from(token.expires_at);
+    let last_used = token
+        .last_used_at
+        .map(|t| {
+            let t: DateTime<Local> = DateTime::from(t);
+            format!("last used {}", t.format("%d %b, %Y, %H:%M"))
+        })
+        .unwrap_or_else(|| "never used".to_string());
+    let operations = token
+        .operations
+        .iter()
+        .map(|o| o.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    view! {
+        <li class="flex justify-between items-center gap-4 py-2">
+            <div>
+                <p class="font-semibold">{token.name.clone()}</p>
+                <p class="text-sm font-extralight">
+                    {format!("{} · {} · ", token.scope, operations)}
+                    {if token.is_expired() { "expired" } else { "expires" }}
+                    {format!(" {} · {}", expires_at.format("%d %b, %Y"), last_used)}
+                </p>
+            </div>
+            <button
+                class="text-sm border-gray-300 hover:border-gray-400 rounded py-1 px-2 border"
+                on:click=move |_| {
+                    let (owner, repo) = split_repo(&repo.get_untracked());
+                    revoke
+                        .dispatch(RevokeToken {
+                            owner,
+                            repo,
+                            scope: token.scope.clone(),
+                            id: token.id.clone(),
+                        });
+                }
+            >
+
+                Revoke
+            </button>
+        </li>
+    }
+}
+
+#[component]
+pub fn ApiTokens(repo: ReadSignal<String>) -> impl IntoView {
+    let create = create_server_action::<CreateToken>();
+    let revoke = create_server_action::<RevokeToken>();
+    provide_context(revoke);
+
+    let tokens = create_resource(
+        move || (repo.get(), create.version().get(), revoke.version().get()),
+        |(repo, _, _)| {
+            let (owner, repo) = split_repo(&repo);
+            list_tokens(owner, repo)
+        },
+    );
+
+    let (name, set_name) = create_signal(String::new());
+    let (org_wide, set_org_wide) = create_signal(false);
+    let (expires_in_days, set_expires_in_days) = create_signal(90i64);
+    let (operations, set_operations) = create_signal(Operation::all());
+
+    view! {
+        <div class="flex flex-col gap-6">
+            <Transition fallback=move || view! { <p>"Loading..."</p> }>
+                {move || {
+                    tokens
+                        .get()
+                        .map(|t| match t {
+                            Ok(t) if t.is_empty() => view! { <p>No API tokens yet.</p> }.into_view(),
+                            Ok(t) => {
+                                view! {
+                                    <ul class="divide-y divide-gray-300 dark:divide-gray-600">
+                                        <For
+                                            each=move || t.clone()
+                                            key=|t| t.id.clone()
+                                            children=move |t: ApiToken| {
+                                                view! { <TokenRow token=t repo=repo/> }
+                                            }
+                                        />
+
+                                    </ul>
+                                }
+                                    .into_view()
+                            }
+                            Err(e) => {
+                                view! { <p>Something went wrong: {format!("{e}")}</p> }.into_view()
+                            }
+                        })
+                }}
+
+            </Transition>
+            <div class="flex flex-col gap-2">
+                <h3 class="font-bold">New token</h3>
+                <input
+                    type="text"
+                    placeholder="Name, e.g. buildkite"
+                    class="rounded-md border border-input bg-white dark:bg-gray-900 px-3 py-2 text-sm"
+                    prop:value=name
+                    on:input=move |ev| set_name(event_target_value(&ev))
+                />
+                <label class="text-sm">
+                    "Expires in (days) "
+                    <input
+                        type="number"
+                        min="1"
+                        max="365"
+                        class="w-20 rounded-md border border-input bg-white dark:bg-gray-900 px-2 py-1"
+                        prop:value=move || expires_in_days().to_string()
+                        on:input=move |ev| {
+                            if let Ok(v) = event_target_value(&ev).parse() {
+                                set_expires_in_days(v);
+                            }
+                        }
+                    />
+
+                </label>
+                <label class="text-sm">
+                    <input
+                        type="checkbox"
+                        class="mr-2"
+                        prop:checked=org_wide
+                        on:change=move |ev| set_org_wide(event_target_checked(&ev))
+                    />
+                    "Usable for every repository in the organisation (organisation admins only)"
+                </label>
+                <For
+                    each=Operation::all
+                    key=|o| o.to_string()
+                    children=move |o: Operation| {
+                        view! {
+                            <label class="text-sm">
+                                <input
+                                    type="checkbox"
+                                    class="mr-2"
+                                    prop:checked=move || operations().contains(&o)
+                                    on:change=move |ev| {
+                                        let checked = event_target_checked(&ev);
+                                        set_operations
+                                            .update(|ops| {
+                                                ops.retain(|op| *op != o);
+                                                if checked {
+                                                    ops.push(o);
+                                                }
+                                            });
+                                    }
+                                />
+
+                                {o.to_string()}
+                            </label>
+                        }
+                    }
+                />
+
+                <button
+                    class="bg-gray-800 dark:bg-gray-900 mt-2 text-white font-semibold py-2 px-4 rounded"
+                    on:click=move |_| {
+                        let (owner, repo) = split_repo(&repo.get_untracked());
+                        create
+                            .dispatch(CreateToken {
+                                owner,
+                                repo,
+                                name: name.get_untracked(),
+                                org_wide: org_wide.get_untracked(),
+                                operations: operations.get_untracked(),
+                                expires_in_days: expires_in_days.get_untracked(),
+                            });
+                        set_name(String::new());
+                    }
+                >
+
+                    Create token
+                </button>
+                {move || {
+                    create
+                        .value()
+                        .get()
+                        .map(|v| match v {
+                            Ok(created) => {
+                                view! {
+                                    <p class="text-sm">
+                                        "Copy this token now, it will not be shown again: "
+                                        <code class="break-all">{created.secret}</code>
+                                    </p>
+                                }
+                                    .into_view()
+                            }
+                            Err(e) => {
+                                view! { <p class="text-sm text-red-500">{format!("{e}")}</p> }
+                                    .into_view()
+                            }
+                        })
+                }}
+
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,495 @@
+use super::components::{use_workflow_events, Header};
+use super::format_duration;
+use crate::workflow::config::CONFIG_PATH;
+use crate::workflow::{self, Environment, EnvironmentStatus, Run, Workflow};
+use chrono::{DateTime, Local, Utc};
+use leptos::*;
+use leptos_meta::Title;
+use leptos_router::{use_params_map, A};
+use std::time::Duration;
+
+#[server(GetWorkflow)]
+pub async fn get_workflow(
+    owner: String,
+    repo: String,
+    id: i64,
+) -> Result<workflow::Workflow, ServerFnError> {
+    use http::StatusCode;
+    use leptos_axum::ResponseOptions;
+
+    crate::auth::require_repo_access(&owner, &repo).await?;
+
+    let created_at =
+        DateTime::from_timestamp(id, 0).ok_or_else(|| ServerFnError::new("invalid workflow id"))?;
+
+    match workflow::client()
+        .await
+        .get(&owner, &repo, created_at)
+        .await
+    {
+        Ok(Some(w)) => Ok(w),
+        Ok(None) => {
+            expect_context::<ResponseOptions>().set_status(StatusCode::NOT_FOUND);
+            Err(ServerFnError::new("workflow not found"))
+        }
+        Err(e) => {
+            log::error!("failed to get workflow: {:#}", e);
+            Err(ServerFnError::new("unable to get workflow"))
+        }
+    }
+}
+
+fn format_time(t: Option<DateTime<Utc>>) -> String {
+    t.map(|t| {
+        let t: DateTime<Local> = DateTime::from(t);
+        t.format("%d %b, %H:%M:%S").to_string()
+    })
+    .unwrap_or_else(|| "-".to_string())
+}
+
+#[component]
+fn TimelineEntry(
+    environment: Environment,
+    owner: String,
+    repo: String,
+    now: ReadSignal<DateTime<Utc>>,
+) -> impl IntoView {
+    let status = environment.status;
+    let started_at = format_time(environment.started_at);
+    let finished_at = format_time(environment.finished_at);
+    let runs = environment.runs.clone();
+    let attempts = environment.attempts.clone();
+    let e = environment.clone();
+    let duration = move || {
+        e.duration(now())
+            .map(format_duration)
+            .unwrap_or_else(|| "-".to_string())
+    };
+
+    view! {
+        <li class="relative pl-8 pb-8">
+            <span
+                class="absolute -left-2 top-1 h-4 w-4 rounded-full"
+                class=("bg-green-500", move || status == EnvironmentStatus::Success)
+                class=("bg-green-500", move || status == EnvironmentStatus::Queued)
+                class=("bg-red-500", move || status == EnvironmentStatus::Failure)
+                class=("bg-yellow-500", move || status == EnvironmentStatus::Running)
+                class=("bg-gray-500", move || status == EnvironmentStatus::Pending)
+                class=("bg-gray-400", move || status == EnvironmentStatus::Cancelled)
+                class=("bg-purple-500", move || status == EnvironmentStatus::Skipped)
+            ></span>
+            <h3 class="font-bold">{environment.name.clone()}</h3>
+            <p class="text-sm">{format!("{status:?}")}</p>
+            <dl class="mt-2 grid grid-cols-2 sm:grid-cols-4 gap-2 text-sm">
+                <div>
+                    <dt class="font-extralight">Started</dt>
+                    <dd>{started_at}</dd>
+                </div>
+                <div>
+                    <dt class="font-extralight">Finished</dt>
+                    <dd>{finished_at}</dd>
+                </div>
+                <div>
+                    <dt class="font-extralight">Duration</dt>
+                    <dd>{duration}</dd>
+                </div>
+                <div>
+                    <dt class="font-extralight">Deployment</dt>
+                    <dd>
+                        {environment
+                            .deployment_id
+                            .map(|id| {
+                                view! {
+                                    <a
+                                        class="underline"
+                                        rel="external noopener"
+                                        href=format!(
+                                            "https://github.com/{}/{}/deployments/{}",
+                                            owner,
+                                            repo,
+                                            environment.name,
+                                        )
+                                    >
+
+                                        {format!("#{id}")}
+                                    </a>
+                                }
+                                    .into_view()
+                            })
+                            .unwrap_or_else(|| "-".into_view())}
+
+                    </dd>
+                </div>
+            </dl>
+            <ul class="mt-2 text-sm">
+                <For
+                    each=move || runs.clone()
+                    key=|r| r.id
+                    children=move |r: Run| {
+                        view! {
+                            <li>
+                                <a class="underline" rel="external noopener" href=r.url>
+                                    {r.name}
+                                </a>
+                            </li>
+                        }
+                    }
+                />
+
+            </ul>
+            {(!attempts.is_empty())
+                .then(|| {
+                    view! {
+                        <details class="mt-2 text-sm">
+                            <summary class="cursor-pointer">
+                                {format!("{} previous attempts", attempts.len())}
+                            </summary>
+                            <ol class="mt-1 list-decimal pl-6">
+                                {attempts
+                                    .iter()
+                                    .map(|a| {
+                                        let duration = a
+                                            .started_at
+                                            .zip(a.finished_at)
+                                            .map(|(s, f)| format_duration(f - s))
+                                            .unwrap_or_else(|| "-".to_string());
+                                        view! {
+                                            <li>
+                                                {format!(
+                                                    "{:?} · started {} · took {}",
+                                                    a.status,
+                                                    format_time(a.started_at),
+                                                    duration,
+                                                )}
+                                                {a
+                                                    .runs
+                                                    .iter()
+                                                    .map(|r| {
+                                                        view! {
+                                                            " · "
+                                                            <a
+                                                                class="underline"
+                                                                rel="external noopener"
+                                                                href=r.url.clone()
+                                                            >
+                                                                {r.name.clone()}
+                                                            </a>
+                                                        }
+                                                    })
+                                                    .collect_view()}
+                                            </li>
+                                        }
+                                    })
+                                    .collect_view()}
+                            </ol>
+                        </details>
+                    }
+                })}
+
+        </li>
+    }
+}
+
+#[component]
+fn Detail(workflow: Workflow, now: ReadSignal<DateTime<Utc>>) -> impl IntoView {
+    let created_at: DateTime<Local> = DateTime::from(workflow.created_at.to_dt());
+    let stable_at = workflow.stable_at();
+    let next = workflow.next_environment().map(|(_, e)| e.name.clone());
+    let owner = workflow.owner.clone();
+    let repo = workflow.repo.clone();
+    let environments = workflow.environments.clone();
+
+    view! {
+        <Title text=workflow.commit_message.clone()/>
+        <div class="px-6 py-8 flex flex-col gap-8">
+            <div>
+                <A href=format!("/dashboard/{}/{}", owner, repo) class="text-sm underline">
+                    {format!("{}/{}", workflow.owner, workflow.repo)}
+                </A>
+                <h1 class="text-2xl font-bold mt-2">{workflow.commit_message.clone()}</h1>
+                <p class="text-sm font-extralight">
+                    {format!(
+                        "{} · {} · created {} · {}",
+                        workflow.git_ref,
+                        workflow.sha.chars().take(7).collect::<String>(),
+                        created_at.format("%d %b, %Y, %H:%M"),
+                        workflow.status,
+                    )}
+
+                </p>
+                {workflow
+                    .rollback_of_path()
+                    .map(|path| {
+                        view! {
+                            <p class="text-sm font-extralight">
+                                "Rollback of " <A class="underline" href=path>
+                                    "an earlier workflow"
+                                </A>
+                            </p>
+                        }
+                    })}
+                {workflow
+                    .config
+                    .is_some()
+                    .then(|| {
+                        view! {
+                            <p class="text-sm font-extralight">
+                                "Pipeline configured by "
+                                <span class="font-mono">{CONFIG_PATH}</span>
+                            </p>
+                        }
+                    })}
+                {(!workflow.directives.is_empty())
+                    .then(|| {
+                        view! {
+                            <p class="text-sm font-extralight">
+                                "Directives: "
+                                <span class="font-mono">
+                                    {workflow
+                                        .directives
+                                        .iter()
+                                        .map(|d| d.to_string())
+                                        .collect::<Vec<_>>()
+                                        .join(" ")}
+                                </span>
+                            </p>
+                        }
+                    })}
+                {workflow
+                    .approval
+                    .clone()
+                    .map(|a| {
+                        let at: DateTime<Local> = DateTime::from(a.at);
+                        view! {
+                            <p class="text-sm font-extralight">
+                                {format!("Approved by {} on {}", a.by, at.format("%d %b, %H:%M"))}
+                            </p>
+                        }
+                    })}
+                {workflow
+                    .awaiting_approval()
+                    .then(|| {
+                        view! {
+                            <p class="text-sm font-semibold text-orange-500">
+                                "Waiting for approval on the dashboard"
+                            </p>
+                        }
+                    })}
+                {workflow
+                    .required_checks
+                    .clone()
+                    .map(|c| {
+                        view! {
+                            <p
+                                class="text-sm"
+                                class=("font-extralight", c.passed())
+                                class=("text-red-500", !c.failed.is_empty())
+                                class=("text-yellow-500", !c.waiting.is_empty())
+                            >
+                                {c.describe()}
+                            </p>
+                        }
+                    })}
+
+            </div>
+            {(!workflow.overrides.is_empty())
+                .then(|| {
+                    view! {
+                        <div class="p-4 rounded-lg bg-purple-100 dark:bg-purple-900 text-sm">
+                            <h2 class="font-bold mb-1">Overrides</h2>
+                            <ul>
+                                {workflow
+                                    .overrides
+                                    .iter()
+                                    .map(|o| {
+                                        let at: DateTime<Local> = DateTime::from(o.at);
+                                        view! {
+                                            <li>
+                                                {format!(
+                                                    "{} · {}",
+                                                    o.describe(),
+                                                    at.format("%d %b, %H:%M"),
+                                                )}
+                                            </li>
+                                        }
+                                    })
+                                    .collect_view()}
+                            </ul>
+                        </div>
+                    }
+                })}
+
+            {stable_at
+                .map(|stable_at| {
+                    let next = next.clone().unwrap_or_default();
+                    view! {
+                        <div class="p-4 rounded-lg bg-yellow-100 dark:bg-yellow-900 text-sm">
+                            {move || {
+                                let remaining = stable_at - now();
+                                if remaining > chrono::Duration::zero() {
+                                    format!(
+                                        "Stability period: {next} starts in {}",
+                                        format_duration(remaining),
+                                    )
+                                } else {
+                                    format!("Stability period over, {next} starts shortly")
+                                }
+                            }}
+
+                        </div>
+                    }
+                })}
+
+            {workflow
+                .provenance
+                .clone()
+                .map(|p| {
+                    view! {
+                        <div class="text-sm">
+                            <h2 class="font-bold text-lg mb-1">Provenance</h2>
+                            <p>
+                                {format!(
+                                    "Triggered by {} on {} of {} by ",
+                                    p.actor,
+                                    p.event_name,
+                                    p.git_ref,
+                                )}
+                                <a class="underline" rel="external noopener" href=p.run_url()>
+                                    {p.workflow_ref.clone()}
+                                </a>
+                            </p>
+                        </div>
+                    }
+                })}
+
+            <div class="flex flex-wrap gap-2">
+                <a
+                    class="text-sm border-gray-300 hover:border-gray-400 rounded py-1 px-2 border"
+                    rel="external noopener"
+                    href=format!("https://github.com/{}/{}/commit/{}", owner, repo, workflow.sha)
+                >
+                    View commit
+                </a>
+            </div>
+            <ol class="border-l border-gray-300 dark:border-gray-600 ml-2">
+                <For
+                    each=move || environments.clone()
+                    key=|e| e.name.clone()
+                    children=move |e: Environment| {
+                        view! {
+                            <TimelineEntry
+                                environment=e
+                                owner=owner.clone()
+                                repo=repo.clone()
+                                now=now
+                            />
+                        }
+                    }
+                />
+
+            </ol>
+        </div>
+    }
+}
+
+#[component]
+pub fn WorkflowDetail() -> impl IntoView {
+    let params = use_params_map();
+    let workflow = create_resource(
+        move || {
+            params.with(|p| {
+                (
+                    p.get("owner").cloned().unwrap_or_default(),
+                    p.get("repo").cloned().unwrap_or_default(),
+                    p.get("workflow").cloned().unwrap_or_default(),
+                )
+            })
+        },
+        |(owner, repo, id)| async move {
+            let id = id
+                .parse::<i64>()
+                .map_err(|_| ServerFnError::new("invalid workflow id"))?;
+            get_workflow(owner, repo, id).await
+        },
+    );
+
+    let repo = Signal::derive(move || {
+        params.with(|p| {
+            format!(
+                "{}/{}",
+                p.get("owner").cloned().unwrap_or_default(),
+                p.get("repo").cloned().unwrap_or_default()
+            )
+        })
+    });
+    let streaming = use_workflow_events(
+        repo,
+        move |w: Workflow| {
+            workflow.update(|current| {
+                if let Some(Ok(current)) = current {
+                    if current.created_at == w.created_at {
+                        *current = w;
+                    }
+                }
+            });
+        },
+        move || workflow.refetch(),
+    );
+    create_effect(move |_| {
+        let handle = set_interval_with_handle(
+            move || {
+                if !streaming.get_untracked() {
+                    workflow.refetch();
+                }
+            },
+            Duration::from_secs(5),
+        )
+        .expect("interval to be created");
+
+        on_cleanup(move || {
+            handle.clear();
+        })
+    });
+
+    // Ticks every second, to keep durations and the stability countdown current.
+    let (now, set_now) = create_signal(Utc::now());
+    create_effect(move |_| {
+        let handle = set_interval_with_handle(
+            move || {
+                set_now(Utc::now());
+            },
+            Duration::from_secs(1),
+        )
+        .expect("interval to be created");
+
+        on_cleanup(move || {
+            handle.clear();
+        })
+    });
+
+    view! {
+        <div class="min-h-screen bg-gray-100 dark:bg-gray-800 dark:text-white">
+            <Header/>
+            <main class="max-w-4xl mx-auto w-full">
+                <Transition fallback=move || view! { <p>"Loading..."</p> }>
+                    {move || {
+                        workflow
+                            .get()
+                            .map(|w| match w {
+                                Ok(w) => view! { <Detail workflow=w now=now/> }.into_view(),
+                                Err(e) => {
+                                    view! {
+                                        <p class="px-6 py-8">
+                                            Something went wrong: {format!("{e}")}
+                                        </p>
+                                    }
+                                        .into_view()
+                                }
+                            })
+                    }}
+
+                </Transition>
+            </main>
+        </div>
+    }
+}
@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+
+/// Which environments workflows for branches matching a pattern may deploy to, in
+/// pipeline order.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BranchRule {
+    /// A branch name where `*` matches any run of characters, e.g. `main` or `release/*`.
+    pub pattern: String,
+    pub environments: Vec<String>,
+}
+
+impl BranchRule {
+    pub fn matches(&self, branch: &str) -> bool {
+        glob_matches(&self.pattern, branch)
+    }
+
+    /// How specific the pattern is, so that `release/*` takes precedence over `*`.
+    fn specificity(&self) -> usize {
+        self.pattern.chars().filter(|c| *c != '*').count()
+    }
+}
+
+/// Matches `text` against `pattern`, where `*` matches any run of characters, `/`
+/// included.
+pub fn glob_matches(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts = parts.collect::<Vec<_>>();
+    let Some((last, middle)) = parts.split_last() else {
+        // No wildcard, so the whole text must have been matched.
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// The branch a git ref points at, or the ref itself if it isn't a branch.
+fn branch_name(git_ref: &str) -> &str {
+    git_ref.strip_prefix("refs/heads/").unwrap_or(git_ref)
+}
+
+/// Works out the pipeline for a workflow on `git_ref` under the repository's rules. With
+/// no rules every pipeline is allowed. Otherwise the most specific matching rule decides:
+/// an empty request gets the rule's pipeline, and any other request must only use
+/// environments the rule allows.
+pub fn resolve_pipeline(
+    rules: &[BranchRule],
+    git_ref: &str,
+    requested: Vec<String>,
+) -> Result<Vec<String>, String> {
+    if rules.is_empty() {
+        return Ok(requested);
+    }
+
+    let branch = branch_name(git_ref);
+    let Some(rule) = rules
+        .iter()
+        .filter(|r| r.matches(branch))
+        .rev()
+        .max_by_key(|r| r.specificity())
+    else {
+        return Err(format!("no pipeline is allowed for {branch}"));
+    };
+
+    if requested.is_empty() {
+        return Ok(rule.environments.clone());
+    }
+    match requested.iter().find(|e| !rule.environments.contains(e)) {
+        Some(e) => Err(format!(
+            "{e} is not allowed for {branch}, which may only deploy to {}",
+            rule.environments.join(", ")
+        )),
+        None => Ok(requested),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str, environments: &[&str]) -> BranchRule {
+        BranchRule {
+            pattern: pattern.to_string(),
+            environments: environments.iter().map(|e| e.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("main", "main"));
+        assert!(!glob_matches("main", "main2"));
+        assert!(glob_matches("release/*", "release/1.2"));
+        assert!(!glob_matches("release/*", "releases"));
+        assert!(glob_matches("*", "feature/login"));
+        assert!(glob_matches("feature/*-fix", "feature/login-fix"));
+        assert!(!glob_matches("feature/*-fix", "feature/login-fixes"));
+        assert!(glob_matches("a*a", "aa"));
+        assert!(!glob_matches("a*a", "a"));
+    }
+
+    #[test]
+    fn test_resolve_pipeline() {
+        let rules = vec![
+            rule("*", &["preview"]),
+            rule("main", &["staging", "production"]),
+            rule("release/*", &["release-candidate"]),
+        ];
+        let envs = |e: &[&str]| e.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            resolve_pipeline(&rules, "refs/heads/main", vec![]),
+            Ok(envs(&["staging", "production"]))
+        );
+        assert_eq!(
+            resolve_pipeline(&rules, "refs/heads/release/1.2", vec![]),
+            Ok(envs(&["release-candidate"]))
+        );
+        assert_eq!(
+            resolve_pipeline(&rules, "refs/heads/feature/login", envs(&["preview"])),
+            Ok(envs(&["preview"]))
+        );
+        assert!(
+            resolve_pipeline(&rules, "refs/heads/feature/login", envs(&["production"])).is_err()
+        );
+        assert!(resolve_pipeline(&rules[1..], "refs/heads/feature/login", vec![]).is_err());
+        assert_eq!(
+            resolve_pipeline(&[], "refs/heads/feature/login", envs(&["production"])),
+            Ok(envs(&["production"]))
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+
+use super::{BranchRule, RepoSettings};
+use crate::auth::Role;
+use crate::aws::{config, to_attribute_value, DynamodbClient, DynamodbError};
+use crate::workflow::DirectiveKind;
+use anyhow::Context;
+use serde::Serialize;
+use tokio::sync::OnceCell;
+
+pub struct Client {
+    table: DynamodbClient,
+}
+
+impl Client {
+    async fn new() -> Client {
+        let table_name =
+            std::env::var("DYNAMODB_SETTINGS").expect("DYNAMODB_SETTINGS is required but not set");
+
+        Client {
+            table: DynamodbClient::new(config().await, table_name),
+        }
+    }
+
+    /// Gets the settings of the repository, falling back to the defaults if none have
+    /// been saved.
+    pub async fn get(&self, owner: &str, repo: &str) -> Result<RepoSettings, anyhow::Error> {
+        let id = format!("{owner}/{repo}");
+        let mut key = HashMap::new();
+        key.insert("id".to_string(), id.clone());
+
+        match self.table.get_item(key).await {
+            Ok(settings) => Ok(settings),
+            Err(DynamodbError::NotFound()) => Ok(RepoSettings {
+                id,
+                ..Default::default()
+            }),
+            Err(e) => Err(e).context("get repo settings"),
+        }
+    }
+
+    /// Sets one attribute of the repository's settings, leaving the others to anyone
+    /// changing them at the same time.
+    async fn set(
+        &self,
+        owner: &str,
+        repo: &str,
+        attribute: &str,
+        value: impl Serialize,
+    ) -> Result<(), anyhow::Error> {
+        self.table
+            .run_update::<RepoSettings>(
+                self.table
+                    .update()
+                    .key("id", to_attribute_value(format!("{owner}/{repo}"))?)
+                    .update_expression("SET #attribute = :value")
+                    .expression_attribute_names("#attribute", attribute)
+                    .expression_attribute_values(":value", to_attribute_value(value)?),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn update_role_grant(
+        &self,
+        owner: &str,
+        repo: &str,
+        login: &str,
+        role: Option<Role>,
+    ) -> Result<(), anyhow::Error> {
+        let id = format!("{owner}/{repo}");
+        // Grants are set within the map, so it has to exist first.
+        self.table
+            .run_update::<RepoSettings>(
+                self.table
+                    .update()
+                    .key("id", to_attribute_value(&id)?)
+                    .update_expression("SET #role_grants = if_not_exists(#role_grants, :empty)")
+                    .expression_attribute_names("#role_grants", "role_grants")
+                    .expression_attribute_values(
+                        ":empty",
+                        to_attribute_value(HashMap::<String, Role>::new())?,
+                    ),
+            )
+            .await
+            .context("updating role grant")?;
+
+        let update = self
+            .table
+            .update()
+            .key("id", to_attribute_value(&id)?)
+            .expression_attribute_names("#role_grants", "role_grants")
+            .expression_attribute_names("#login", login);
+        let update = match role {
+            Some(role) => update
+                .update_expression("SET #role_grants.#login = :role")
+                .expression_attribute_values(":role", to_attribute_value(role)?),
+            None => update.update_expression("REMOVE #role_grants.#login"),
+        };
+        self.table
+            .run_update::<RepoSettings>(update)
+            .await
+            .context("updating role grant")?;
+
+        Ok(())
+    }
+
+    pub async fn update_protected_environments(
+        &self,
+        owner: &str,
+        repo: &str,
+        environments: Vec<String>,
+    ) -> Result<(), anyhow::Error> {
+        self.set(owner, repo, "protected_environments", environments)
+            .await
+            .context("updating protected environments")
+    }
+
+    pub async fn update_ignored_directives(
+        &self,
+        owner: &str,
+        repo: &str,
+        directives: Vec<DirectiveKind>,
+    ) -> Result<(), anyhow::Error> {
+        self.set(owner, repo, "ignored_directives", directives)
+            .await
+            .context("updating ignored directives")
+    }
+
+    pub async fn update_branch_rules(
+        &self,
+        owner: &str,
+        repo: &str,
+        rules: Vec<BranchRule>,
+    ) -> Result<(), anyhow::Error> {
+        self.set(owner, repo, "branch_rules", rules)
+            .await
+            .context("updating branch rules")
+    }
+
+    pub async fn update_required_contexts(
+        &self,
+        owner: &str,
+        repo: &str,
+        contexts: Vec<String>,
+    ) -> Result<(), anyhow::Error> {
+        self.set(owner, repo, "required_contexts", contexts)
+            .await
+            .context("updating required contexts")
+    }
+}
+
+pub async fn client() -> &'static Client {
+    static CONFIG: OnceCell<Client> = OnceCell::const_new();
+    CONFIG.get_or_init(Client::new).await
+}
@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+
+use crate::auth::Role;
+use crate::workflow::DirectiveKind;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+mod branches;
+
+pub use branches::*;
+
+#[cfg(feature = "ssr")]
+mod client;
+
+#[cfg(feature = "ssr")]
+pub use client::*;
+
+/// Per-repository pipedream configuration.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RepoSettings {
+    /// Composite of owner/repo.
+    pub id: String,
+    /// Roles granted to GitHub users on top of their GitHub repository permissions,
+    /// keyed by login.
+    #[serde(default)]
+    pub role_grants: HashMap<String, Role>,
+    /// Environments that can never be skipped when promoting a workflow.
+    #[serde(default)]
+    pub protected_environments: Vec<String>,
+    /// Commit message and label directives that don't affect workflows. Every directive
+    /// is honoured unless listed here.
+    #[serde(default)]
+    pub ignored_directives: Vec<DirectiveKind>,
+    /// Which environments each branch may deploy to. With none, any branch may deploy
+    /// anywhere.
+    #[serde(default)]
+    pub branch_rules: Vec<BranchRule>,
+    /// Commit statuses and check runs that must pass before a workflow deploys its first
+    /// environment.
+    #[serde(default)]
+    pub required_contexts: Vec<String>,
+}
+
+#[server(ListRoleGrants)]
+pub async fn list_role_grants(
+    owner: String,
+    repo: String,
+) -> Result<Vec<(String, Role)>, ServerFnError> {
+    crate::auth::require_action(&owner, &repo, crate::auth::Action::ManageRoles).await?;
+
+    let settings = client().await.get(&owner, &repo).await.map_err(|e| {
+        log::error!("failed to get repo settings: {:#}", e);
+        ServerFnError::new("unable to list role grants")
+    })?;
+
+    let mut grants = settings.role_grants.into_iter().collect::<Vec<_>>();
+    grants.sort();
+    Ok(grants)
+}
+
+/// Grants the user a role on the repository, or removes their grant when `role` is `None`.
+#[server(UpdateRoleGrant)]
+pub async fn update_role_grant(
+    owner: String,
+    repo: String,
+    login: String,
+    role: Option<Role>,
+) -> Result<(), ServerFnError> {
+    let login = login.trim().to_string();
+    if login.is_empty() {
+        return Err(ServerFnError::new("a GitHub login is required"));
+    }
+
+    let session =
+        crate::auth::require_action(&owner, &repo, crate::auth::Action::ManageRoles).await?;
+
+    client()
+        .await
+        .update_role_grant(&owner, &repo, &login, role)
+        .await
+        .map_err(|e| {
+            log::error!("failed to update role grant: {:#}", e);
+            ServerFnError::new("unable to update role grant")
+        })?;
+
+    log::info!(
+        "{} set role of {login} on {owner}/{repo} to {role:?}",
+        session.user.login
+    );
+    crate::auth::forget_roles(&owner, &repo);
+
+    Ok(())
+}
+
+#[server(ListProtectedEnvironments)]
+pub async fn list_protected_environments(
+    owner: String,
+    repo: String,
+) -> Result<Vec<String>, ServerFnError> {
+    crate::auth::require_action(&owner, &repo, crate::auth::Action::ManageSettings).await?;
+
+    let settings = client().await.get(&owner, &repo).await.map_err(|e| {
+        log::error!("failed to get repo settings: {:#}", e);
+        ServerFnError::new("unable to list protected environments")
+    })?;
+
+    Ok(settings.protected_environments)
+}
+
+#[server(UpdateProtectedEnvironments)]
+pub async fn update_protected_environments(
+    owner: String,
+    repo: String,
+    environments: Vec<String>,
+) -> Result<(), ServerFnError> {
+    let session =
+        crate::auth::require_action(&owner, &repo, crate::auth::Action::ManageSettings).await?;
+
+    let environments = environments
+        .into_iter()
+        .map(|e| e.trim().to_string())
+        .filter(|e| !e.is_empty())
+        .collect::<Vec<_>>();
+
+    client()
+        .await
+        .update_protected_environments(&owner, &repo, environments.clone())
+        .await
+        .map_err(|e| {
+            log::error!("failed to update protected environments: {:#}", e);
+            ServerFnError::new("unable to update protected environments")
+        })?;
+
+    log::info!(
+        "{} set protected environments of {owner}/{repo} to {environments:?}",
+        session.user.login
+    );
+
+    Ok(())
+}
+
+#[server(ListIgnoredDirectives)]
+pub async fn list_ignored_directives(
+    owner: String,
+    repo: String,
+) -> Result<Vec<DirectiveKind>, ServerFnError> {
+    crate::auth::require_action(&owner, &repo, crate::auth::Action::ManageSettings).await?;
+
+    let settings = client().await.get(&owner, &repo).await.map_err(|e| {
+        log::error!("failed to get repo settings: {:#}", e);
+        ServerFnError::new("unable to list ignored directives")
+    })?;
+
+    Ok(settings.ignored_directives)
+}
+
+#[server(UpdateIgnoredDirectives)]
+pub async fn update_ignored_directives(
+    owner: String,
+    repo: String,
+    directives: Vec<DirectiveKind>,
+) -> Result<(), ServerFnError> {
+    let session =
+        crate::auth::require_action(&owner, &repo, crate::auth::Action::ManageSettings).await?;
+
+    client()
+        .await
+        .update_ignored_directives(&owner, &repo, directives.clone())
+        .await
+        .map_err(|e| {
+            log::error!("failed to update ignored directives: {:#}", e);
+            ServerFnError::new("unable to update ignored directives")
+        })?;
+
+    log::info!(
+        "{} set ignored directives of {owner}/{repo} to {directives:?}",
+        session.user.login
+    );
+
+    Ok(())
+}
+
+#[server(ListBranchRules)]
+pub async fn list_branch_rules(
+    owner: String,
+    repo: String,
+) -> Result<Vec<BranchRule>, ServerFnError> {
+    crate::auth::require_action(&owner, &repo, crate::auth::Action::ManageSettings).await?;
+
+    let settings = client().await.get(&owner, &repo).await.map_err(|e| {
+        log::error!("failed to get repo settings: {:#}", e);
+        ServerFnError::new("unable to list branch rules")
+    })?;
+
+    Ok(settings.branch_rules)
+}
+
+#[server(UpdateBranchRules)]
+pub async fn update_branch_rules(
+    owner: String,
+    repo: String,
+    rules: Vec<BranchRule>,
+) -> Result<(), ServerFnError> {
+    let session =
+        crate::auth::require_action(&owner, &repo, crate::auth::Action::ManageSettings).await?;
+
+    let rules = rules
+        .into_iter()
+        .map(|r| BranchRule {
+            pattern: r.pattern.trim().to_string(),
+            environments: r
+                .environments
+                .into_iter()
+                .map(|e| e.trim().to_string())
+                .filter(|e| !e.is_empty())
+                .collect(),
+        })
+        .filter(|r| !r.pattern.is_empty())
+        .collect::<Vec<_>>();
+
+    client()
+        .await
+        .update_branch_rules(&owner, &repo, rules.clone())
+        .await
+        .map_err(|e| {
+            log::error!("failed to update branch rules: {:#}", e);
+            ServerFnError::new("unable to update branch rules")
+        })?;
+
+    log::info!(
+        "{} set branch rules of {owner}/{repo} to {rules:?}",
+        session.user.login
+    );
+
+    Ok(())
+}
+
+#[server(ListRequiredContexts)]
+pub async fn list_required_contexts(
+    owner: String,
+    repo: String,
+) -> Result<Vec<String>, ServerFnError> {
+    crate::auth::require_action(&owner, &repo, crate::auth::Action::ManageSettings).await?;
+
+    let settings = client().await.get(&owner, &repo).await.map_err(|e| {
+        log::error!("failed to get repo settings: {:#}", e);
+        ServerFnError::new("unable to list required checks")
+    })?;
+
+    Ok(settings.required_contexts)
+}
+
+#[server(UpdateRequiredContexts)]
+pub async fn update_required_contexts(
+    owner: String,
+    repo: String,
+    contexts: Vec<String>,
+) -> Result<(), ServerFnError> {
+    let session =
+        crate::auth::require_action(&owner, &repo, crate::auth::Action::ManageSettings).await?;
+
+    let contexts = contexts
+        .into_iter()
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+        .collect::<Vec<_>>();
+
+    client()
+        .await
+        .update_required_contexts(&owner, &repo, contexts.clone())
+        .await
+        .map_err(|e| {
+            log::error!("failed to update required contexts: {:#}", e);
+            ServerFnError::new("unable to update required checks")
+        })?;
+
+    log::info!(
+        "{} set required contexts of {owner}/{repo} to {contexts:?}",
+        session.user.login
+    );
+
+    Ok(())
+}
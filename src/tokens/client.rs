@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use super::{ApiToken, Operation, TOKEN_PREFIX};
+use crate::aws::{config, to_attribute_value, DynamodbClient, DynamodbError};
+use crate::crypto;
+use anyhow::Context;
+use chrono::{Duration, Utc};
+use tokio::sync::OnceCell;
+
+pub struct Client {
+    table: DynamodbClient,
+}
+
+pub struct CreateTokenRequest {
+    pub scope: String,
+    pub name: String,
+    pub operations: Vec<Operation>,
+    pub expires_in_days: i64,
+}
+
+impl Client {
+    async fn new() -> Client {
+        let table_name =
+            std::env::var("DYNAMODB_TOKENS").expect("DYNAMODB_TOKENS is required but not set");
+
+        Client {
+            table: DynamodbClient::new(config().await, table_name),
+        }
+    }
+
+    /// Creates a new token, returning the token itself alongside what was stored. The
+    /// token cannot be recovered afterwards.
+    pub async fn create(
+        &self,
+        req: CreateTokenRequest,
+    ) -> Result<(String, ApiToken), anyhow::Error> {
+        let secret = format!("{TOKEN_PREFIX}{}", crypto::random_hex(32)?);
+        let now = Utc::now();
+        let token = ApiToken {
+            id: crypto::sha256_hex(&secret),
+            scope: req.scope,
+            name: req.name,
+            operations: req.operations,
+            created_at: now,
+            expires_at: now + Duration::days(req.expires_in_days),
+            last_used_at: None,
+        };
+
+        self.table
+            .put_item(token.clone())
+            .await
+            .context("create token")?;
+
+        Ok((secret, token))
+    }
+
+    /// Looks up the token matching the given secret, if there is one.
+    pub async fn get(&self, secret: &str) -> Result<Option<ApiToken>, anyhow::Error> {
+        let mut key = HashMap::new();
+        key.insert("id".to_string(), crypto::sha256_hex(secret));
+
+        match self.table.get_item(key).await {
+            Ok(token) => Ok(Some(token)),
+            Err(DynamodbError::NotFound()) => Ok(None),
+            Err(e) => Err(e).context("get token"),
+        }
+    }
+
+    pub async fn list(&self, scope: &str) -> Result<Vec<ApiToken>, anyhow::Error> {
+        self.table
+            .run_query(
+                self.table
+                    .query()
+                    .index_name("tokens_by_scope")
+                    .scan_index_forward(false)
+                    .key_condition_expression("#scope = :scope")
+                    .expression_attribute_names("#scope", "scope")
+                    .expression_attribute_values(":scope", to_attribute_value(scope)?),
+            )
+            .await
+            .context("list tokens")
+    }
+
+    /// Deletes the token with the given id, provided it belongs to the given scope.
+    pub async fn revoke(&self, scope: &str, id: &str) -> Result<(), DynamodbError> {
+        self.table
+            .run_delete(
+                self.table
+                    .delete()
+                    .key("id", to_attribute_value(id).context("marshalling id")?)
+                    .condition_expression("#scope = :scope")
+                    .expression_attribute_names("#scope", "scope")
+                    .expression_attribute_values(
+                        ":scope",
+                        to_attribute_value(scope).context("marshalling scope")?,
+                    ),
+            )
+            .await
+    }
+
+    pub async fn record_use(&self, id: &str) -> Result<(), anyhow::Error> {
+        self.table
+            .run_update::<ApiToken>(
+                self.table
+                    .update()
+                    .key("id", to_attribute_value(id)?)
+                    .update_expression("SET #last_used_at = :last_used_at")
+                    .condition_expression("attribute_exists(#id)")
+                    .expression_attribute_names("#last_used_at", "last_used_at")
+                    .expression_attribute_names("#id", "id")
+                    .expression_attribute_values(":last_used_at", to_attribute_value(Utc::now())?),
+            )
+            .await
+            .context("recording token use")?;
+
+        Ok(())
+    }
+}
+
+pub async fn client() -> &'static Client {
+    static CONFIG: OnceCell<Client> = OnceCell::const_new();
+    CONFIG.get_or_init(Client::new).await
+}
@@ -0,0 +1,64 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+mod client;
+
+#[cfg(feature = "ssr")]
+pub use client::*;
+
+/// Every API token starts with this prefix, which is how they are told apart from
+/// GitHub Actions OIDC tokens.
+pub const TOKEN_PREFIX: &str = "pd_";
+
+/// An operation that an API token can be allowed to perform.
+#[derive(Debug, Clone, Serialize, Deserialize, Copy, PartialEq, Eq)]
+pub enum Operation {
+    CreateWorkflow,
+    CancelWorkflow,
+}
+
+impl Operation {
+    pub fn all() -> Vec<Operation> {
+        vec![Operation::CreateWorkflow, Operation::CancelWorkflow]
+    }
+}
+
+impl Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Operation::CreateWorkflow => "Create workflow",
+            Operation::CancelWorkflow => "Cancel workflow",
+        })
+    }
+}
+
+/// An API token, as stored. The token itself is only ever shown once, on creation, and
+/// we only keep its SHA-256 hash, which doubles as the id.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ApiToken {
+    pub id: String,
+    /// Either `owner/repo`, or just `owner` for a token usable across an organisation.
+    pub scope: String,
+    pub name: String,
+    pub operations: Vec<Operation>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl ApiToken {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at < Utc::now()
+    }
+
+    pub fn covers(&self, owner: &str, repo: &str) -> bool {
+        self.scope == owner || self.scope == format!("{owner}/{repo}")
+    }
+
+    pub fn allows(&self, owner: &str, repo: &str, operation: Operation) -> bool {
+        !self.is_expired() && self.covers(owner, repo) && self.operations.contains(&operation)
+    }
+}
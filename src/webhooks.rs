@@ -0,0 +1,496 @@
+//! Creates workflows from the webhooks GitHub sends the pipedream app, so a repository
+//! with a `[trigger]` in its `.pipedream.toml` doesn't need a CI step calling
+//! `/api/workflow`.
+
+use crate::crypto;
+use crate::github;
+use crate::workflow::config::TriggerEvent;
+use crate::workflow::{self, CreateError, NewWorkflow, Provenance};
+use anyhow::Context;
+use axum::body::Bytes;
+use http::{HeaderMap, StatusCode};
+use serde::Deserialize;
+
+/// Where GitHub delivers the app's webhooks.
+pub const WEBHOOK_PATH: &str = "/api/github/webhook";
+
+#[derive(Debug, Deserialize)]
+struct Owner {
+    login: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Repository {
+    name: String,
+    full_name: String,
+    owner: Owner,
+}
+
+/// The repository a commit was pushed to, which for a pull request from a fork is the
+/// fork rather than the repository the webhook is for.
+#[derive(Debug, Deserialize)]
+struct HeadRepository {
+    full_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct HeadCommit {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PushEvent {
+    #[serde(rename = "ref")]
+    git_ref: String,
+    after: String,
+    #[serde(default)]
+    deleted: bool,
+    head_commit: Option<HeadCommit>,
+    repository: Repository,
+}
+
+#[derive(Debug, Deserialize)]
+struct CheckSuite {
+    conclusion: Option<String>,
+    head_branch: Option<String>,
+    head_sha: String,
+    head_commit: Option<HeadCommit>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CheckSuiteEvent {
+    action: String,
+    check_suite: CheckSuite,
+    repository: Repository,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkflowRun {
+    id: u64,
+    name: Option<String>,
+    path: String,
+    event: String,
+    conclusion: Option<String>,
+    head_branch: Option<String>,
+    head_sha: String,
+    head_commit: Option<HeadCommit>,
+    head_repository: Option<HeadRepository>,
+    actor: Owner,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkflowRunEvent {
+    action: String,
+    workflow_run: WorkflowRun,
+    repository: Repository,
+}
+
+/// A commit a webhook says is ready to deploy, if the repository's trigger agrees.
+#[derive(Debug, PartialEq, Eq)]
+struct Candidate {
+    event: TriggerEvent,
+    owner: String,
+    repo: String,
+    branch: String,
+    sha: String,
+    commit_message: String,
+    /// The GitHub Actions workflow that passed, for `workflow_run`.
+    workflow: Option<String>,
+    provenance: Option<Provenance>,
+    /// Whether the event doesn't say which repository the commit was pushed to, so it
+    /// has to be checked for on the branch before deploying.
+    check_on_branch: bool,
+}
+
+impl Candidate {
+    fn git_ref(&self) -> String {
+        format!("refs/heads/{}", self.branch)
+    }
+}
+
+/// Checks the `X-Hub-Signature-256` header GitHub signs each delivery with.
+fn verify_signature(secret: &[u8], signature: &str, body: &[u8]) -> Result<(), anyhow::Error> {
+    let Some(signature) = signature.strip_prefix("sha256=") else {
+        anyhow::bail!("malformed signature");
+    };
+    let expected = crypto::hmac_sha256_hex(secret, body)?;
+    if !crypto::constant_time_eq(&expected, signature) {
+        anyhow::bail!("invalid signature");
+    }
+    Ok(())
+}
+
+/// Whether the commit was pushed to the repository itself. Runs for pull requests from
+/// forks name the fork's branch, which may share its name with one that deploys.
+fn same_repository(head: &Option<HeadRepository>, repository: &Repository) -> bool {
+    head.as_ref()
+        .is_some_and(|h| h.full_name == repository.full_name)
+}
+
+/// Reads a delivery into the commit it's about, or `None` for events that never deploy
+/// anything, like deleted branches, tags, failed runs and runs for forks.
+fn parse_event(event: &str, body: &[u8]) -> Result<Option<Candidate>, serde_json::Error> {
+    let message = |c: Option<HeadCommit>| c.map(|c| c.message).unwrap_or_default();
+    let succeeded = |conclusion: &Option<String>| conclusion.as_deref() == Some("success");
+
+    let candidate = match event {
+        "push" => {
+            let push: PushEvent = serde_json::from_slice(body)?;
+            let Some(branch) = push.git_ref.strip_prefix("refs/heads/") else {
+                return Ok(None);
+            };
+            if push.deleted {
+                return Ok(None);
+            }
+            Candidate {
+                event: TriggerEvent::Push,
+                owner: push.repository.owner.login,
+                repo: push.repository.name,
+                branch: branch.to_string(),
+                sha: push.after,
+                commit_message: message(push.head_commit),
+                workflow: None,
+                provenance: None,
+                check_on_branch: false,
+            }
+        }
+        "check_suite" => {
+            let event: CheckSuiteEvent = serde_json::from_slice(body)?;
+            let suite = event.check_suite;
+            let Some(branch) = suite.head_branch else {
+                return Ok(None);
+            };
+            if event.action != "completed" || !succeeded(&suite.conclusion) {
+                return Ok(None);
+            }
+            Candidate {
+                event: TriggerEvent::CheckSuite,
+                owner: event.repository.owner.login,
+                repo: event.repository.name,
+                branch,
+                sha: suite.head_sha,
+                commit_message: message(suite.head_commit),
+                workflow: None,
+                provenance: None,
+                // Suites for pull requests from forks have the fork's branch, and
+                // nothing to tell them apart from suites for the repository's own.
+                check_on_branch: true,
+            }
+        }
+        "workflow_run" => {
+            let event: WorkflowRunEvent = serde_json::from_slice(body)?;
+            let run = event.workflow_run;
+            let Some(branch) = run.head_branch else {
+                return Ok(None);
+            };
+            if event.action != "completed"
+                || !succeeded(&run.conclusion)
+                || !same_repository(&run.head_repository, &event.repository)
+            {
+                return Ok(None);
+            }
+            let git_ref = format!("refs/heads/{branch}");
+            let provenance = Provenance {
+                actor: run.actor.login,
+                workflow_ref: format!("{}/{}@{git_ref}", event.repository.full_name, run.path),
+                repository: event.repository.full_name,
+                run_id: run.id.to_string(),
+                event_name: run.event,
+                git_ref,
+            };
+            // Only runs for pushes are for the branch's own commits, not pull requests.
+            if !provenance.is_push_to(&branch) {
+                return Ok(None);
+            }
+            Candidate {
+                event: TriggerEvent::WorkflowRun,
+                provenance: Some(provenance),
+                owner: event.repository.owner.login,
+                repo: event.repository.name,
+                branch,
+                sha: run.head_sha,
+                commit_message: message(run.head_commit),
+                workflow: run.name,
+                check_on_branch: false,
+            }
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(candidate))
+}
+
+/// Creates a workflow for the commit if the repository's trigger asks for one, returning
+/// whether it did.
+async fn deploy(candidate: Candidate) -> Result<bool, CreateError> {
+    // Before reading the configuration, which a fork could have changed.
+    if candidate.check_on_branch
+        && !github::is_commit_on_branch(
+            &candidate.owner,
+            &candidate.repo,
+            &candidate.sha,
+            &candidate.branch,
+        )
+        .await?
+    {
+        log::info!(
+            "ignoring webhook for {} on {}/{}, which isn't on branch {}",
+            candidate.sha,
+            candidate.owner,
+            candidate.repo,
+            candidate.branch
+        );
+        return Ok(false);
+    }
+
+    let config = workflow::fetch_config(&candidate.owner, &candidate.repo, &candidate.sha).await?;
+    let triggered = config
+        .as_ref()
+        .and_then(|c| c.trigger.as_ref())
+        .is_some_and(|t| {
+            t.matches(
+                candidate.event,
+                &candidate.branch,
+                candidate.workflow.as_deref(),
+            )
+        });
+    if !triggered {
+        return Ok(false);
+    }
+
+    // Nothing to do if the sha already has a workflow, as when GitHub redelivers a
+    // webhook or CI also calls `/api/workflow`.
+    let workflow = match workflow::create(
+        NewWorkflow {
+            git_ref: candidate.git_ref(),
+            owner: candidate.owner,
+            repo: candidate.repo,
+            sha: candidate.sha,
+            commit_message: candidate.commit_message,
+            labels: vec![],
+            environments: vec![],
+            stability_period_minutes: None,
+            provenance: candidate.provenance,
+            once_per_sha: true,
+        },
+        config,
+    )
+    .await
+    {
+        Ok(workflow) => workflow,
+        Err(CreateError::AlreadyExists(_)) => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    log::info!(
+        "created workflow {} for {}/{} from a webhook",
+        workflow.id,
+        workflow.owner,
+        workflow.repo
+    );
+    Ok(true)
+}
+
+/// Handles a webhook delivery from GitHub. The response status only shows up in the
+/// app's delivery log, so failures are also logged here.
+pub async fn handle(headers: HeaderMap, body: Bytes) -> StatusCode {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+    let secret = match std::env::var("GITHUB_WEBHOOK_SECRET")
+        .context("GITHUB_WEBHOOK_SECRET is required but not set")
+    {
+        Ok(secret) => secret,
+        Err(e) => {
+            log::error!("failed to handle webhook: {:#}", e);
+            return StatusCode::SERVICE_UNAVAILABLE;
+        }
+    };
+    let signature = header("x-hub-signature-256").unwrap_or_default();
+    if let Err(e) = verify_signature(secret.as_bytes(), signature, &body) {
+        log::info!("rejected webhook: {:#}", e);
+        return StatusCode::UNAUTHORIZED;
+    }
+
+    let event = header("x-github-event").unwrap_or_default();
+    let candidate = match parse_event(event, &body) {
+        Ok(Some(candidate)) => candidate,
+        Ok(None) => return StatusCode::NO_CONTENT,
+        Err(e) => {
+            log::info!("failed to parse {event} webhook: {:#}", e);
+            return StatusCode::BAD_REQUEST;
+        }
+    };
+
+    match deploy(candidate).await {
+        Ok(true) => StatusCode::CREATED,
+        Ok(false) => StatusCode::NO_CONTENT,
+        Err(CreateError::Other(e)) => {
+            log::error!("failed to create workflow from webhook: {:#}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+        Err(e) => {
+            log::info!("not creating workflow from webhook: {e}");
+            StatusCode::UNPROCESSABLE_ENTITY
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A `check_suite` delivery as GitHub sends it, trimmed of URLs.
+    const CHECK_SUITE_COMPLETED: &str = r#"{
+  "action": "completed",
+  "check_suite": {
+    "id": 118578147,
+    "node_id": "MDEwOkNoZWNrU3VpdGUxMTg1NzgxNDc=",
+    "head_branch": "main",
+    "head_sha": "ec26c3e57ca3a959ca5aad62de7213c562f8c821",
+    "status": "completed",
+    "conclusion": "success",
+    "before": "ebe1b3eb7bbb6ba6ec8ab9e4ab0b8cc0c4ec0ec8",
+    "after": "ec26c3e57ca3a959ca5aad62de7213c562f8c821",
+    "pull_requests": [],
+    "app": {
+      "id": 2,
+      "slug": "octoapp",
+      "node_id": "MDExOkludGVncmF0aW9uMQ==",
+      "owner": {
+        "login": "Octocat",
+        "id": 1,
+        "type": "User",
+        "site_admin": false
+      },
+      "name": "Octocat App",
+      "events": []
+    },
+    "created_at": "2019-05-15T15:20:31Z",
+    "updated_at": "2019-05-15T15:21:14Z",
+    "rerequestable": true,
+    "runs_rerequestable": true,
+    "latest_check_runs_count": 1,
+    "head_commit": {
+      "id": "ec26c3e57ca3a959ca5aad62de7213c562f8c821",
+      "tree_id": "31b122c26a97cf9af023e9ddab94a82c6e77b0ea",
+      "message": "Update README.md",
+      "timestamp": "2019-05-15T15:20:30Z",
+      "author": {
+        "name": "Codertocat",
+        "email": "21031067+Codertocat@users.noreply.github.com"
+      },
+      "committer": {
+        "name": "Codertocat",
+        "email": "21031067+Codertocat@users.noreply.github.com"
+      }
+    }
+  },
+  "repository": {
+    "id": 186853002,
+    "node_id": "MDEwOlJlcG9zaXRvcnkxODY4NTMwMDI=",
+    "name": "Hello-World",
+    "full_name": "Codertocat/Hello-World",
+    "private": false,
+    "owner": {
+      "login": "Codertocat",
+      "id": 21031067,
+      "node_id": "MDQ6VXNlcjIxMDMxMDY3",
+      "type": "User",
+      "site_admin": false
+    },
+    "fork": false,
+    "default_branch": "main"
+  },
+  "sender": {
+    "login": "Codertocat",
+    "id": 21031067,
+    "type": "User",
+    "site_admin": false
+  }
+}"#;
+
+    #[test]
+    fn test_verify_signature() {
+        let body = br#"{"zen":"Keep it logically awesome."}"#;
+        let signature = format!(
+            "sha256={}",
+            crypto::hmac_sha256_hex(b"secret", body).unwrap()
+        );
+
+        assert!(verify_signature(b"secret", &signature, body).is_ok());
+        assert!(verify_signature(b"other", &signature, body).is_err());
+        assert!(verify_signature(b"secret", &signature, b"{}").is_err());
+        assert!(
+            verify_signature(b"secret", signature.trim_start_matches("sha256="), body).is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_event() {
+        let repository =
+            r#"{"name":"pipedream","full_name":"doug/pipedream","owner":{"login":"doug"}}"#;
+
+        let push = format!(
+            r#"{{"ref":"refs/heads/main","after":"abc","deleted":false,"head_commit":{{"message":"Fix it"}},"repository":{repository}}}"#
+        );
+        let candidate = parse_event("push", push.as_bytes()).unwrap().unwrap();
+        assert_eq!(candidate.event, TriggerEvent::Push);
+        assert_eq!(candidate.git_ref(), "refs/heads/main");
+        assert_eq!(candidate.sha, "abc");
+        assert_eq!(candidate.commit_message, "Fix it");
+        assert_eq!(candidate.owner, "doug");
+
+        let tag = push.replace("refs/heads/main", "refs/tags/v1");
+        assert_eq!(parse_event("push", tag.as_bytes()).unwrap(), None);
+
+        let run = |conclusion: &str| {
+            format!(
+                r#"{{"action":"completed","workflow_run":{{"id":42,"name":"CI","path":".github/workflows/ci.yml","event":"push","conclusion":"{conclusion}","head_branch":"main","head_sha":"abc","head_commit":{{"message":"Fix it"}},"head_repository":{{"full_name":"doug/pipedream"}},"actor":{{"login":"doug"}}}},"repository":{repository}}}"#
+            )
+        };
+        let candidate = parse_event("workflow_run", run("success").as_bytes())
+            .unwrap()
+            .unwrap();
+        assert_eq!(candidate.workflow.as_deref(), Some("CI"));
+        let provenance = candidate.provenance.unwrap();
+        assert_eq!(
+            provenance.run_url(),
+            "https://github.com/doug/pipedream/actions/runs/42"
+        );
+        assert!(provenance.is_push_to("main"));
+        assert_eq!(
+            parse_event("workflow_run", run("failure").as_bytes()).unwrap(),
+            None
+        );
+
+        // A pull request from a fork's main branch mustn't deploy this repository's main.
+        let fork = run("success").replace(
+            r#""head_repository":{"full_name":"doug/pipedream"}"#,
+            r#""head_repository":{"full_name":"mallory/pipedream"}"#,
+        );
+        assert_eq!(parse_event("workflow_run", fork.as_bytes()).unwrap(), None);
+        let pull_request = run("success").replace(r#""event":"push""#, r#""event":"pull_request""#);
+        assert_eq!(
+            parse_event("workflow_run", pull_request.as_bytes()).unwrap(),
+            None
+        );
+
+        // Check suites don't say which repository their commit came from, so whether it
+        // is on the branch is left to `deploy`.
+        let candidate = parse_event("check_suite", CHECK_SUITE_COMPLETED.as_bytes())
+            .unwrap()
+            .unwrap();
+        assert_eq!(candidate.event, TriggerEvent::CheckSuite);
+        assert_eq!(candidate.git_ref(), "refs/heads/main");
+        assert_eq!(candidate.sha, "ec26c3e57ca3a959ca5aad62de7213c562f8c821");
+        assert_eq!(candidate.commit_message, "Update README.md");
+        assert_eq!(
+            (candidate.owner.as_str(), candidate.repo.as_str()),
+            ("Codertocat", "Hello-World")
+        );
+        assert!(candidate.check_on_branch);
+        let failed = CHECK_SUITE_COMPLETED
+            .replace(r#""conclusion": "success""#, r#""conclusion": "failure""#);
+        assert_eq!(parse_event("check_suite", failed.as_bytes()).unwrap(), None);
+
+        assert_eq!(parse_event("issues", b"{}").unwrap(), None);
+    }
+}
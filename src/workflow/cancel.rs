@@ -0,0 +1,56 @@
+use super::{EnvironmentStatus, Workflow};
+use crate::github;
+use chrono::Utc;
+
+/// Cancels a running or paused workflow, stopping the processor from starting any more
+/// of its environments. The environment in flight has its GitHub deployment marked as
+/// errored and its GitHub Actions runs cancelled. Returns `None` if the workflow has
+/// already finished.
+pub async fn cancel(workflow: Workflow) -> Result<Option<Workflow>, anyhow::Error> {
+    let mut environments = workflow.environments.clone();
+    let in_flight = workflow
+        .next_environment()
+        .filter(|(_, e)| e.started_at.is_some())
+        .map(|(idx, _)| idx);
+    if let Some(environment) = in_flight.and_then(|idx| environments.get_mut(idx)) {
+        environment.status = EnvironmentStatus::Cancelled;
+        environment.finished_at = Some(Utc::now());
+    }
+
+    let Some(workflow) = super::client().await.cancel(workflow, environments).await? else {
+        return Ok(None);
+    };
+
+    // The workflow is cancelled as far as pipedream is concerned, so failing to tidy up
+    // on GitHub is only logged.
+    if let Some(environment) = in_flight.and_then(|idx| workflow.environments.get(idx)) {
+        if let Some(deployment_id) = environment.deployment_id {
+            if let Err(e) = github::update_deployment_status(
+                &workflow.owner,
+                &workflow.repo,
+                &deployment_id,
+                &workflow.deployment_status(
+                    &environment.name,
+                    github::DeploymentStatus::Error,
+                    "cancelled",
+                ),
+            )
+            .await
+            {
+                log::error!("failed to mark deployment as errored: {:#}", e);
+            }
+        }
+
+        for run in &environment.runs {
+            if let Err(e) =
+                github::cancel_workflow_run(&workflow.owner, &workflow.repo, run.id).await
+            {
+                log::warn!("failed to cancel workflow run {}: {:#}", run.id, e);
+            }
+        }
+    }
+
+    super::check_run::sync(&workflow).await;
+
+    Ok(Some(workflow))
+}
@@ -0,0 +1,290 @@
+//! Mirrors a workflow's progress in a check run on its commit, so it shows up on the
+//! commit and pull request pages on GitHub.
+
+use super::{format_duration, Environment, EnvironmentStatus, Status, Workflow};
+use crate::github::{
+    self, CheckRun, CheckRunConclusion, CheckRunOutput, CheckRunStatus, CheckRunUpdate,
+};
+use chrono::{DateTime, Utc};
+
+/// The name the check run is shown under on GitHub.
+const CHECK_RUN_NAME: &str = "pipedream";
+
+fn status_label(status: EnvironmentStatus) -> &'static str {
+    match status {
+        EnvironmentStatus::Pending => "⏸️ Pending",
+        EnvironmentStatus::Queued => "⏳ Queued",
+        EnvironmentStatus::Running => "🚀 Deploying",
+        EnvironmentStatus::Success => "✅ Deployed",
+        EnvironmentStatus::Failure => "❌ Failed",
+        EnvironmentStatus::Cancelled => "🚫 Cancelled",
+        EnvironmentStatus::Skipped => "⏭️ Skipped",
+    }
+}
+
+/// Escapes text for a markdown table cell.
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+/// A one line account of where the workflow is, e.g. `Deploying to staging`.
+fn title(workflow: &Workflow, now: DateTime<Utc>) -> String {
+    match workflow.status {
+        Status::Cancelled => "Cancelled".to_string(),
+        Status::Success => {
+            let deployed = workflow
+                .environments
+                .iter()
+                .filter(|e| e.status == EnvironmentStatus::Success)
+                .map(|e| e.name.as_str())
+                .collect::<Vec<_>>();
+            match deployed.last() {
+                Some(last) => format!("Deployed to {last}"),
+                None => "Nothing deployed".to_string(),
+            }
+        }
+        Status::Failure => match &workflow.required_checks {
+            Some(checks) if !checks.failed.is_empty() => "Required checks failed".to_string(),
+            _ => match workflow.failed_environment() {
+                Some((_, e)) => format!("Failed deploying to {}", e.name),
+                None => "Failed".to_string(),
+            },
+        },
+        Status::Paused if workflow.awaiting_approval() => "Waiting for approval".to_string(),
+        Status::Paused => "Paused".to_string(),
+        Status::Running => match workflow.next_environment() {
+            Some((_, next)) => match (workflow.stable_at(), &workflow.required_checks) {
+                (Some(stable_at), _) => format!(
+                    "Deploying to {} in {}",
+                    next.name,
+                    format_duration(stable_at - now)
+                ),
+                (None, Some(checks)) if !checks.waiting.is_empty() => {
+                    format!("Waiting for checks before deploying to {}", next.name)
+                }
+                _ => format!("Deploying to {}", next.name),
+            },
+            None => "Finishing".to_string(),
+        },
+    }
+}
+
+fn environment_row(environment: &Environment, now: DateTime<Utc>) -> String {
+    let started = environment
+        .started_at
+        .map(|s| s.format("%d %b, %H:%M UTC").to_string())
+        .unwrap_or_default();
+    let duration = environment
+        .duration(now)
+        .map(format_duration)
+        .unwrap_or_default();
+    let runs = environment
+        .runs
+        .iter()
+        .map(|r| format!("[{}]({})", cell(&r.name), r.url))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "| {} | {} | {started} | {duration} | {runs} |",
+        cell(&environment.name),
+        status_label(environment.status)
+    )
+}
+
+/// Renders the workflow as the markdown shown on the check run's page.
+fn summary(workflow: &Workflow, url: &str, now: DateTime<Utc>) -> String {
+    let mut lines = vec![
+        format!("[View the workflow on pipedream]({url})"),
+        String::new(),
+        "| Environment | Status | Started | Duration | Runs |".to_string(),
+        "| --- | --- | --- | --- | --- |".to_string(),
+    ];
+    lines.extend(
+        workflow
+            .environments
+            .iter()
+            .map(|e| environment_row(e, now)),
+    );
+
+    let mut notes = vec![];
+    if let Some(checks) = &workflow.required_checks {
+        notes.push(checks.describe());
+    }
+    if let Some(approval) = &workflow.approval {
+        notes.push(format!("Approved by {}", approval.by));
+    }
+    notes.extend(workflow.overrides.iter().map(|o| o.describe()));
+    if !notes.is_empty() {
+        lines.push(String::new());
+        lines.extend(notes.into_iter().map(|n| format!("- {n}")));
+    }
+
+    lines.join("\n")
+}
+
+fn check_run(workflow: &Workflow, url: String, now: DateTime<Utc>) -> CheckRunUpdate {
+    let conclusion = match workflow.status {
+        Status::Success => Some(CheckRunConclusion::Success),
+        Status::Failure => Some(CheckRunConclusion::Failure),
+        Status::Cancelled => Some(CheckRunConclusion::Cancelled),
+        Status::Running | Status::Paused => None,
+    };
+    let started_at = workflow
+        .environments
+        .iter()
+        .filter_map(|e| e.started_at)
+        .min();
+    let status = match (conclusion, started_at) {
+        (Some(_), _) => CheckRunStatus::Completed,
+        (None, Some(_)) => CheckRunStatus::InProgress,
+        (None, None) => CheckRunStatus::Queued,
+    };
+
+    CheckRunUpdate {
+        name: CHECK_RUN_NAME.to_string(),
+        status,
+        conclusion,
+        external_id: workflow.path(),
+        started_at,
+        completed_at: conclusion.map(|_| workflow.updated_at.unwrap_or(now)),
+        output: CheckRunOutput {
+            title: title(workflow, now),
+            summary: summary(workflow, &url, now),
+        },
+        details_url: url,
+    }
+}
+
+/// The workflow's own run among the commit's runs of the check, which other workflows
+/// for the same commit have runs of too.
+fn existing_run(runs: &[CheckRun], external_id: &str) -> Option<u64> {
+    runs.iter()
+        .find(|r| r.external_id.as_deref() == Some(external_id))
+        .map(|r| r.id)
+}
+
+/// Updates the check run an earlier sync created but couldn't store the id of, or
+/// creates one if there isn't one, returning its id.
+async fn find_or_create(
+    workflow: &Workflow,
+    update: &CheckRunUpdate,
+) -> Result<u64, anyhow::Error> {
+    let runs = github::list_named_check_runs(
+        &workflow.owner,
+        &workflow.repo,
+        &workflow.sha,
+        CHECK_RUN_NAME,
+    )
+    .await?;
+    match existing_run(&runs, &update.external_id) {
+        Some(id) => {
+            github::update_check_run(&workflow.owner, &workflow.repo, id, update).await?;
+            Ok(id)
+        }
+        None => {
+            github::create_check_run(&workflow.owner, &workflow.repo, &workflow.sha, update).await
+        }
+    }
+}
+
+/// Creates or updates the workflow's check run to match its progress. The check run is
+/// only for display, so failing to update it is logged rather than failing the workflow.
+pub(crate) async fn sync(workflow: &Workflow) {
+    let update = check_run(workflow, workflow.url(), Utc::now());
+    let result = match workflow.check_run_id {
+        Some(id) => github::update_check_run(&workflow.owner, &workflow.repo, id, &update).await,
+        None => match find_or_create(workflow, &update).await {
+            Ok(id) => super::client().await.set_check_run_id(workflow, id).await,
+            Err(e) => Err(e),
+        },
+    };
+    if let Err(e) = result {
+        log::error!("failed to sync check run: {:#}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::{environment, workflow};
+    use super::super::Run;
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_check_run() {
+        let now = Utc::now();
+        let mut staging = environment("staging", EnvironmentStatus::Success);
+        staging.started_at = Some(now - Duration::minutes(10));
+        staging.finished_at = Some(now - Duration::minutes(5));
+        staging.runs = vec![Run {
+            id: 1,
+            name: "Deploy | staging".to_string(),
+            url: "https://github.com/owner/repo/actions/runs/1".to_string(),
+        }];
+        let mut soaking = workflow(
+            Status::Running,
+            vec![
+                staging,
+                environment("production", EnvironmentStatus::Pending),
+            ],
+        );
+        soaking.due_to_run = now + Duration::minutes(25);
+
+        let update = check_run(&soaking, "https://pipedream.test/w".to_string(), now);
+        assert_eq!(update.status, CheckRunStatus::InProgress);
+        assert_eq!(update.conclusion, None);
+        assert_eq!(update.started_at, Some(now - Duration::minutes(10)));
+        assert_eq!(update.output.title, "Deploying to production in 25m 0s");
+        assert_eq!(
+            update.output.summary,
+            [
+                "[View the workflow on pipedream](https://pipedream.test/w)",
+                "",
+                "| Environment | Status | Started | Duration | Runs |",
+                "| --- | --- | --- | --- | --- |",
+                &format!(
+                    "| staging | ✅ Deployed | {} | 5m 0s | [Deploy \\| staging](https://github.com/owner/repo/actions/runs/1) |",
+                    (now - Duration::minutes(10)).format("%d %b, %H:%M UTC")
+                ),
+                "| production | ⏸️ Pending |  |  |  |",
+            ]
+            .join("\n")
+        );
+
+        let queued = workflow(
+            Status::Running,
+            vec![environment("staging", EnvironmentStatus::Pending)],
+        );
+        let update = check_run(&queued, String::new(), now);
+        assert_eq!(update.status, CheckRunStatus::Queued);
+        assert_eq!(update.output.title, "Deploying to staging");
+
+        let failed = workflow(
+            Status::Failure,
+            vec![environment("staging", EnvironmentStatus::Failure)],
+        );
+        let update = check_run(&failed, String::new(), now);
+        assert_eq!(update.status, CheckRunStatus::Completed);
+        assert_eq!(update.conclusion, Some(CheckRunConclusion::Failure));
+        assert_eq!(update.completed_at, Some(now));
+        assert_eq!(update.output.title, "Failed deploying to staging");
+    }
+
+    #[test]
+    fn test_existing_run() {
+        let run = |id: u64, external_id: Option<&str>| CheckRun {
+            id,
+            name: CHECK_RUN_NAME.to_string(),
+            status: "in_progress".to_string(),
+            conclusion: None,
+            external_id: external_id.map(|e| e.to_string()),
+        };
+        let runs = vec![
+            run(1, None),
+            run(2, Some("/dashboard/owner/repo/1")),
+            run(3, Some("/dashboard/owner/repo/2")),
+        ];
+        assert_eq!(existing_run(&runs, "/dashboard/owner/repo/2"), Some(3));
+        assert_eq!(existing_run(&runs, "/dashboard/owner/repo/3"), None);
+    }
+}
@@ -0,0 +1,224 @@
+use super::{EnvironmentStatus, RequiredChecks, Status, Workflow};
+use crate::github::{self, CheckRun, CommitState, CommitStatus};
+use anyhow::Context;
+use chrono::{DateTime, Duration, Utc};
+
+/// How long a workflow waits for its required checks before failing.
+const REQUIRED_CHECKS_TIMEOUT_MINUTES: i64 = 60;
+
+/// How often to look at the checks again while waiting on them.
+const POLL_INTERVAL_SECONDS: i64 = 30;
+
+/// Check run conclusions that don't hold up a deployment.
+const PASSING_CONCLUSIONS: [&str; 3] = ["success", "neutral", "skipped"];
+
+/// Sorts the required contexts into those still waiting and those that failed, going by
+/// the commit's statuses and then its check runs. A context neither has reported yet is
+/// waiting.
+fn evaluate(
+    contexts: &[String],
+    statuses: &[CommitStatus],
+    runs: &[CheckRun],
+) -> (Vec<String>, Vec<String>) {
+    let mut waiting = vec![];
+    let mut failed = vec![];
+    for context in contexts {
+        let passed = match statuses.iter().find(|s| s.context == *context) {
+            Some(status) => match status.state {
+                CommitState::Success => Some(true),
+                CommitState::Pending => None,
+                CommitState::Error | CommitState::Failure => Some(false),
+            },
+            None => {
+                let runs = runs
+                    .iter()
+                    .filter(|r| r.name == *context)
+                    .collect::<Vec<_>>();
+                if runs.is_empty() || runs.iter().any(|r| r.status != "completed") {
+                    None
+                } else {
+                    Some(runs.iter().all(|r| {
+                        r.conclusion
+                            .as_deref()
+                            .is_some_and(|c| PASSING_CONCLUSIONS.contains(&c))
+                    }))
+                }
+            }
+        };
+        match passed {
+            Some(true) => {}
+            Some(false) => failed.push(context.clone()),
+            None => waiting.push(context.clone()),
+        }
+    }
+    (waiting, failed)
+}
+
+impl RequiredChecks {
+    /// Checks the contexts again, carrying on from `previous` unless it failed, in which
+    /// case the workflow has been retried and starts waiting afresh. Contexts still waiting
+    /// after `REQUIRED_CHECKS_TIMEOUT_MINUTES` count as failed.
+    fn evaluate(
+        contexts: &[String],
+        previous: Option<&RequiredChecks>,
+        statuses: &[CommitStatus],
+        runs: &[CheckRun],
+        now: DateTime<Utc>,
+    ) -> RequiredChecks {
+        let since = match previous {
+            Some(p) if p.failed.is_empty() => p.since,
+            _ => now,
+        };
+        let (mut waiting, mut failed) = evaluate(contexts, statuses, runs);
+        if !waiting.is_empty() && now - since > Duration::minutes(REQUIRED_CHECKS_TIMEOUT_MINUTES) {
+            failed.append(&mut waiting);
+        }
+
+        RequiredChecks {
+            contexts: contexts.to_vec(),
+            since,
+            waiting,
+            failed,
+        }
+    }
+}
+
+/// Holds the workflow's first environment until the required contexts pass, returning
+/// the workflow once they have, or `None` while they're still running or after they fail
+/// it.
+pub(crate) async fn gate(
+    client: &super::Client,
+    workflow: Workflow,
+    contexts: &[String],
+) -> Result<Option<Workflow>, anyhow::Error> {
+    let statuses = github::list_commit_statuses(&workflow.owner, &workflow.repo, &workflow.sha)
+        .await
+        .context("listing commit statuses")?;
+    let runs = github::list_check_runs(&workflow.owner, &workflow.repo, &workflow.sha)
+        .await
+        .context("listing check runs")?;
+
+    let now = Utc::now();
+    let checks = RequiredChecks::evaluate(
+        contexts,
+        workflow.required_checks.as_ref(),
+        &statuses,
+        &runs,
+        now,
+    );
+    if checks.passed() {
+        let environments = workflow.environments.clone();
+        let due_to_run = workflow.due_to_run;
+        let workflow = client
+            .update_required_checks(workflow, checks, environments, Status::Running, due_to_run)
+            .await?;
+        return Ok(Some(workflow));
+    }
+
+    let mut environments = workflow.environments.clone();
+    let (status, due_to_run) = if checks.failed.is_empty() {
+        log::info!(
+            "workflow for commit sha {} waiting on checks {:?}",
+            &workflow.sha,
+            checks.waiting
+        );
+        (
+            Status::Running,
+            now + Duration::seconds(POLL_INTERVAL_SECONDS),
+        )
+    } else {
+        log::info!(
+            "workflow for commit sha {} failed checks {:?}",
+            &workflow.sha,
+            checks.failed
+        );
+        if let Some(environment) = environments.first_mut() {
+            environment.status = EnvironmentStatus::Failure;
+            environment.finished_at = Some(now);
+        }
+        (Status::Failure, workflow.due_to_run)
+    };
+    let workflow = client
+        .update_required_checks(workflow, checks, environments, status, due_to_run)
+        .await?;
+    super::check_run::sync(&workflow).await;
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(context: &str, state: CommitState) -> CommitStatus {
+        CommitStatus {
+            context: context.to_string(),
+            state,
+        }
+    }
+
+    fn run(name: &str, status: &str, conclusion: Option<&str>) -> CheckRun {
+        CheckRun {
+            id: 1,
+            name: name.to_string(),
+            status: status.to_string(),
+            conclusion: conclusion.map(|c| c.to_string()),
+            external_id: None,
+        }
+    }
+
+    fn contexts(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let statuses = vec![
+            status("ci/build", CommitState::Success),
+            status("ci/lint", CommitState::Pending),
+            status("ci/audit", CommitState::Error),
+        ];
+        let runs = vec![
+            run("test", "completed", Some("success")),
+            run("e2e", "in_progress", None),
+            run("docs", "completed", Some("skipped")),
+            run("deploy-check", "completed", Some("failure")),
+        ];
+
+        assert_eq!(
+            evaluate(&contexts(&["ci/build", "test", "docs"]), &statuses, &runs),
+            (vec![], vec![])
+        );
+        assert_eq!(
+            evaluate(
+                &contexts(&["ci/lint", "e2e", "missing", "ci/audit", "deploy-check"]),
+                &statuses,
+                &runs
+            ),
+            (
+                contexts(&["ci/lint", "e2e", "missing"]),
+                contexts(&["ci/audit", "deploy-check"])
+            )
+        );
+    }
+
+    #[test]
+    fn test_timeout() {
+        let now = Utc::now();
+        let contexts = contexts(&["test"]);
+
+        let waiting = RequiredChecks::evaluate(&contexts, None, &[], &[], now);
+        assert_eq!(waiting.since, now);
+        assert_eq!(waiting.waiting, contexts);
+
+        let later = now + Duration::minutes(REQUIRED_CHECKS_TIMEOUT_MINUTES + 1);
+        let timed_out = RequiredChecks::evaluate(&contexts, Some(&waiting), &[], &[], later);
+        assert_eq!(timed_out.since, now);
+        assert_eq!(timed_out.failed, contexts);
+
+        // Once failed, a retried workflow starts waiting again.
+        let retried = RequiredChecks::evaluate(&contexts, Some(&timed_out), &[], &[], later);
+        assert_eq!(retried.since, later);
+        assert!(retried.failed.is_empty());
+    }
+}
@@ -1,27 +1,66 @@
-use super::{CreatedAt, Environment, EnvironmentStatus, Status, Workflow};
-use crate::aws::{config, to_attribute_value, DynamodbClient};
+use std::collections::HashMap;
+
+use super::{
+    Approval, CreatedAt, Deployment, DeploymentPage, Environment, EnvironmentStatus, Override,
+    RequiredChecks, Status, Workflow, WorkflowFilter, WorkflowPage,
+};
+use crate::aws::{config, to_attribute_value, DynamodbClient, DynamodbError};
+use crate::events;
 use anyhow::Context;
+use aws_sdk_dynamodb::operation::query::builders::QueryFluentBuilder;
 use chrono::{DateTime, Utc};
+use serde::Serialize;
 use tokio::sync::OnceCell;
 
+const DEPLOYMENTS_PAGE_SIZE: usize = 50;
+
+/// Records which workflow first deployed a sha, kept in a partition of its own beside
+/// the repository's workflows. It has no status, so it stays out of the status index.
+#[derive(Serialize)]
+struct CommitClaim {
+    id: String,
+    /// The sha, as the sort key.
+    created_at: String,
+    workflow_created_at: CreatedAt,
+}
+
+/// The workflow was cancelled while being processed, so the processor's update to it
+/// was refused.
+#[derive(thiserror::Error, Debug)]
+#[error("workflow was cancelled")]
+pub struct Cancelled;
+
 pub struct Client {
     table: DynamodbClient,
+    deployments: DynamodbClient,
 }
 
 impl Client {
     async fn new() -> Client {
         let table_name = std::env::var("DYNAMODB_WORKFLOWS")
             .expect("DYNAMODB_WORKFLOWS is required but not set");
+        let deployments_table_name = std::env::var("DYNAMODB_DEPLOYMENTS")
+            .expect("DYNAMODB_DEPLOYMENTS is required but not set");
 
         Client {
             table: DynamodbClient::new(config().await, table_name),
+            deployments: DynamodbClient::new(config().await, deployments_table_name),
         }
     }
 
+    /// Creates the workflow, or returns `None` if the request is `once_per_sha` and the
+    /// sha already has one. Every workflow but a rollback claims its sha, in the same
+    /// write, so a webhook can't create a second one however its deliveries interleave.
     pub async fn create(
         &self,
         workflow: super::CreateWorkflowRequest,
-    ) -> Result<(), anyhow::Error> {
+    ) -> Result<Option<Workflow>, anyhow::Error> {
+        // Workflows that require approval wait, paused, until someone approves them.
+        let status = if workflow.requires_approval() {
+            Status::Paused
+        } else {
+            Status::Running
+        };
         let environments = workflow
             .environments
             .into_iter()
@@ -31,39 +70,220 @@ impl Client {
                 started_at: None,
                 finished_at: None,
                 deployment_id: None,
+                runs: vec![],
+                attempts: vec![],
             })
             .collect::<Vec<_>>();
+        let once_per_sha = workflow.once_per_sha;
+        let workflow = Workflow {
+            id: workflow.owner.clone() + "/" + &workflow.repo,
+            created_at: CreatedAt::now(),
+            git_ref: workflow.git_ref.clone(),
+            owner: workflow.owner.clone(),
+            repo: workflow.repo.clone(),
+            sha: workflow.sha.clone(),
+            stability_period_minutes: workflow.stability_period_minutes,
+            max_stability_period_minutes: workflow.max_stability_period_minutes,
+            environments,
+            status,
+            commit_message: workflow.commit_message.clone(),
+            updated_at: None,
+            due_to_run: Utc::now(),
+            provenance: workflow.provenance,
+            rollback_of: workflow.rollback_of,
+            overrides: vec![],
+            directives: workflow.directives,
+            approval: None,
+            config: workflow.config,
+            required_checks: None,
+            check_run_id: None,
+        };
+
+        let mut items = vec![self
+            .table
+            .transact_put(workflow.clone(), Some("attribute_not_exists(id)"))?];
+        if workflow.rollback_of.is_none() {
+            let claim = CommitClaim {
+                id: format!("{}/{}#commits", workflow.owner, workflow.repo),
+                created_at: workflow.sha.clone(),
+                workflow_created_at: workflow.created_at.clone(),
+            };
+            let condition = once_per_sha.then_some("attribute_not_exists(id)");
+            items.push(self.table.transact_put(claim, condition)?);
+        }
+
+        match self.table.transact_write(items).await {
+            Ok(()) => {}
+            Err(DynamodbError::ConditionFailed(1)) => return Ok(None),
+            Err(DynamodbError::ConditionFailed(_)) => {
+                anyhow::bail!("another workflow was created for the repository at the same time")
+            }
+            Err(e) => return Err(e).context("create workflow"),
+        }
+        events::publish(&workflow);
+
+        Ok(Some(workflow))
+    }
+
+    pub async fn get(
+        &self,
+        owner: &str,
+        repo: &str,
+        created_at: DateTime<Utc>,
+    ) -> Result<Option<Workflow>, anyhow::Error> {
+        let mut key = HashMap::new();
+        key.insert("id".to_string(), format!("{owner}/{repo}"));
+        key.insert("created_at".to_string(), CreatedAt(created_at).to_rfc3339());
+
+        match self.table.get_item(key).await {
+            Ok(workflow) => Ok(Some(workflow)),
+            Err(DynamodbError::NotFound()) => Ok(None),
+            Err(e) => Err(e).context("get workflow"),
+        }
+    }
+
+    /// Builds a query for the repository's workflows matching the filter, newest first.
+    fn filtered_query(
+        &self,
+        owner: &str,
+        repo: &str,
+        filter: &WorkflowFilter,
+    ) -> Result<QueryFluentBuilder, anyhow::Error> {
+        let mut key_condition = "#id = :id".to_string();
+        let mut filters = vec![];
+        let mut query = self
+            .table
+            .query()
+            .scan_index_forward(false)
+            .expression_attribute_names("#id", "id")
+            .expression_attribute_values(":id", to_attribute_value(format!("{owner}/{repo}"))?);
+
+        if filter.from.is_some() || filter.to.is_some() {
+            query = query.expression_attribute_names("#created_at", "created_at");
+        }
+        match (filter.from, filter.to) {
+            (Some(_), Some(_)) => key_condition += " and #created_at between :from and :to",
+            (Some(_), None) => key_condition += " and #created_at >= :from",
+            (None, Some(_)) => key_condition += " and #created_at <= :to",
+            (None, None) => {}
+        }
+        if let Some(from) = filter.from {
+            query = query.expression_attribute_values(
+                ":from",
+                to_attribute_value(CreatedAt(from).to_rfc3339())?,
+            );
+        }
+        if let Some(to) = filter.to {
+            query = query.expression_attribute_values(
+                ":to",
+                to_attribute_value(CreatedAt(to).to_rfc3339())?,
+            );
+        }
+
+        if let Some(status) = filter.status {
+            filters.push("#status = :status");
+            query = query
+                .expression_attribute_names("#status", "status")
+                .expression_attribute_values(":status", to_attribute_value(status)?);
+        }
+        if let Some(git_ref) = filter.git_ref.as_ref().filter(|r| !r.is_empty()) {
+            filters.push("#git_ref = :git_ref");
+            query = query
+                .expression_attribute_names("#git_ref", "git_ref")
+                .expression_attribute_values(":git_ref", to_attribute_value(git_ref)?);
+        }
+        if let Some(search) = filter.search.as_ref().filter(|s| !s.is_empty()) {
+            filters.push("contains(#commit_message, :search)");
+            query = query
+                .expression_attribute_names("#commit_message", "commit_message")
+                .expression_attribute_values(":search", to_attribute_value(search)?);
+        }
+
+        query = query.key_condition_expression(key_condition);
+        if !filters.is_empty() {
+            query = query.filter_expression(filters.join(" and "));
+        }
+
+        Ok(query)
+    }
+
+    /// Lists a page of the repository's workflows matching the filter, newest first.
+    pub async fn list(
+        &self,
+        owner: &str,
+        repo: &str,
+        filter: &WorkflowFilter,
+        cursor: Option<String>,
+        limit: usize,
+    ) -> Result<WorkflowPage, anyhow::Error> {
+        let (workflows, cursor) = self
+            .table
+            .run_query_page(self.filtered_query(owner, repo, filter)?, cursor, limit)
+            .await
+            .context("list workflows")?;
+
+        Ok(WorkflowPage { workflows, cursor })
+    }
+
+    /// Counts the repository's workflows matching the filter.
+    pub async fn count(
+        &self,
+        owner: &str,
+        repo: &str,
+        filter: &WorkflowFilter,
+    ) -> Result<usize, anyhow::Error> {
         self.table
-            .put_item(Workflow {
-                id: workflow.owner.clone() + "/" + &workflow.repo,
-                created_at: CreatedAt::now(),
-                git_ref: workflow.git_ref.clone(),
-                owner: workflow.owner.clone(),
-                repo: workflow.repo.clone(),
-                sha: workflow.sha.clone(),
-                stability_period_minutes: workflow.stability_period_minutes,
-                environments,
-                status: Status::Running,
-                commit_message: workflow.commit_message.clone(),
-                updated_at: None,
-                due_to_run: Utc::now(),
-            })
+            .run_count(self.filtered_query(owner, repo, filter)?)
             .await
-            .context("create workflow")
+            .context("count workflows")
     }
 
-    pub async fn list(&self, owner: String, repo: String) -> Result<Vec<Workflow>, anyhow::Error> {
+    /// Counts the workflows created after `after`, up to and including `until`.
+    pub async fn count_between(
+        &self,
+        owner: &str,
+        repo: &str,
+        after: &CreatedAt,
+        until: &CreatedAt,
+    ) -> Result<usize, anyhow::Error> {
+        // created_at is stored to the second, so this excludes `after` itself.
+        let from = CreatedAt(after.to_dt() + chrono::Duration::seconds(1));
+
         self.table
-            .run_query(
+            .run_count(
                 self.table
                     .query()
-                    .scan_index_forward(false)
-                    .key_condition_expression("#id = :id")
+                    .key_condition_expression("#id = :id and #created_at between :from and :until")
                     .expression_attribute_names("#id", "id")
-                    .expression_attribute_values(":id", to_attribute_value(owner + "/" + &repo)?),
+                    .expression_attribute_names("#created_at", "created_at")
+                    .expression_attribute_values(
+                        ":id",
+                        to_attribute_value(format!("{owner}/{repo}"))?,
+                    )
+                    .expression_attribute_values(":from", to_attribute_value(from.to_rfc3339())?)
+                    .expression_attribute_values(":until", to_attribute_value(until.to_rfc3339())?),
+            )
+            .await
+            .context("count workflows")
+    }
+
+    /// Lists the running workflows of every repository, going by status rather than
+    /// repository so it doesn't page through each repository's history to find them.
+    pub async fn list_running(&self) -> Result<Vec<Workflow>, anyhow::Error> {
+        self.table
+            .run_query(
+                self.table
+                    .query()
+                    .index_name("workflows_by_status")
+                    .key_condition_expression("#status = :status")
+                    .expression_attribute_names("#status", "status")
+                    .expression_attribute_values(
+                        ":status",
+                        to_attribute_value(Status::Running.to_string())?,
+                    ),
             )
             .await
-            .context("list workflows")
+            .context("list running")
     }
 
     pub(crate) async fn get_due_to_run(
@@ -97,14 +317,16 @@ impl Client {
         environments: Vec<Environment>,
         due_to_run: DateTime<Utc>,
     ) -> Result<Workflow, anyhow::Error> {
-        self.table
+        let previous = w.environments.clone();
+        let workflow = self
+            .table
             .run_update(
                 self.table
                     .update()
-                    .key("id", to_attribute_value(w.id)?)
+                    .key("id", to_attribute_value(&w.id)?)
                     .key("created_at", to_attribute_value(w.created_at.to_rfc3339())?)
                     .update_expression("SET #environments = :environments, #due_to_run = :due_to_run, #updated_at = :updated_at, #status = :status")
-                    .condition_expression("attribute_exists(#id) and attribute_exists(#created_at)")
+                    .condition_expression("attribute_exists(#id) and attribute_exists(#created_at) and #status <> :cancelled")
                     .expression_attribute_names("#environments", "environments")
                     .expression_attribute_names("#due_to_run", "due_to_run")
                     .expression_attribute_names("#updated_at", "updated_at")
@@ -113,11 +335,19 @@ impl Client {
                     .expression_attribute_names("#status", "status")
                     .expression_attribute_values(":due_to_run", to_attribute_value(due_to_run)?)
                     .expression_attribute_values(":environments", to_attribute_value(environments)?)
+                    .expression_attribute_values(":cancelled", to_attribute_value(Status::Cancelled)?)
                     .expression_attribute_values(":updated_at", to_attribute_value(Utc::now())?)
                     .expression_attribute_values(":status", to_attribute_value(Status::Failure)?),
             )
-            .await
-            .context("failing environment")
+            .await;
+        let workflow = match workflow {
+            Ok(workflow) => workflow,
+            Err(e) => return Err(self.refused(&w, e).await).context("failing environment"),
+        };
+        events::publish(&workflow);
+        self.record_deployments(&workflow, &previous).await;
+
+        Ok(workflow)
     }
 
     pub(crate) async fn complete_environment(
@@ -126,49 +356,93 @@ impl Client {
         environments: Vec<Environment>,
         due_to_run: DateTime<Utc>,
     ) -> Result<Workflow, anyhow::Error> {
-        self.table
+        let previous = w.environments.clone();
+        let workflow = self
+            .table
             .run_update(
                 self.table
                     .update()
-                    .key("id", to_attribute_value(w.id)?)
+                    .key("id", to_attribute_value(&w.id)?)
                     .key("created_at", to_attribute_value(w.created_at.to_rfc3339())?)
                     .update_expression("SET #environments = :environments, #due_to_run = :due_to_run, #updated_at = :updated_at")
-                    .condition_expression("attribute_exists(#id) and attribute_exists(#created_at)")
+                    .condition_expression("attribute_exists(#id) and attribute_exists(#created_at) and #status <> :cancelled")
                     .expression_attribute_names("#environments", "environments")
                     .expression_attribute_names("#due_to_run", "due_to_run")
                     .expression_attribute_names("#updated_at", "updated_at")
                     .expression_attribute_names("#id", "id")
+                    .expression_attribute_names("#status", "status")
                     .expression_attribute_names("#created_at", "created_at")
                     .expression_attribute_values(":due_to_run", to_attribute_value(due_to_run)?)
                     .expression_attribute_values(":environments", to_attribute_value(environments)?)
+                    .expression_attribute_values(":cancelled", to_attribute_value(Status::Cancelled)?)
                     .expression_attribute_values(":updated_at", to_attribute_value(Utc::now())?),
             )
-            .await
-            .context("completing environment")
+            .await;
+        let workflow = match workflow {
+            Ok(workflow) => workflow,
+            Err(e) => return Err(self.refused(&w, e).await).context("completing environment"),
+        };
+        events::publish(&workflow);
+        self.record_deployments(&workflow, &previous).await;
+
+        Ok(workflow)
     }
 
     pub(crate) async fn mark_workflow_done(
         &self,
         w: Workflow,
         status: Status,
-    ) -> Result<(), anyhow::Error> {
-        self.table
+    ) -> Result<Workflow, anyhow::Error> {
+        let workflow = self
+            .table
             .run_update(
                 self.table
                     .update()
-                    .key("id", to_attribute_value(w.id)?)
+                    .key("id", to_attribute_value(&w.id)?)
                     .key("created_at", to_attribute_value(w.created_at.to_rfc3339())?)
                     .update_expression("SET #status = :status, #updated_at = :updated_at")
-                    .condition_expression("attribute_exists(#id) and attribute_exists(#created_at)")
+                    .condition_expression("attribute_exists(#id) and attribute_exists(#created_at) and #status <> :cancelled")
                     .expression_attribute_names("#status", "status")
                     .expression_attribute_names("#updated_at", "updated_at")
                     .expression_attribute_names("#id", "id")
                     .expression_attribute_names("#created_at", "created_at")
                     .expression_attribute_values(":status", to_attribute_value(status)?)
+                    .expression_attribute_values(":cancelled", to_attribute_value(Status::Cancelled)?)
                     .expression_attribute_values(":updated_at", to_attribute_value(Utc::now())?),
             )
+            .await;
+        let workflow = match workflow {
+            Ok(workflow) => workflow,
+            Err(e) => return Err(self.refused(&w, e).await).context("marking workflow done"),
+        };
+        events::publish(&workflow);
+
+        Ok(workflow)
+    }
+
+    /// Records the id of the workflow's check run, leaving the rest of it untouched.
+    pub(crate) async fn set_check_run_id(
+        &self,
+        w: &Workflow,
+        check_run_id: u64,
+    ) -> Result<(), anyhow::Error> {
+        self.table
+            .run_update::<Workflow>(
+                self.table
+                    .update()
+                    .key("id", to_attribute_value(&w.id)?)
+                    .key("created_at", to_attribute_value(w.created_at.to_rfc3339())?)
+                    .update_expression("SET #check_run_id = :check_run_id")
+                    .condition_expression("attribute_exists(#id)")
+                    .expression_attribute_names("#check_run_id", "check_run_id")
+                    .expression_attribute_names("#id", "id")
+                    .expression_attribute_values(
+                        ":check_run_id",
+                        to_attribute_value(check_run_id)?,
+                    ),
+            )
             .await
-            .context("marking workflow done")?;
+            .context("setting check run id")?;
 
         Ok(())
     }
@@ -184,25 +458,335 @@ impl Client {
             w.created_at.to_rfc3339()
         );
 
-        self.table
+        let previous = w.environments.clone();
+        let workflow = self
+            .table
             .run_update(
                 self.table
                     .update()
-                    .key("id", to_attribute_value(w.id)?)
+                    .key("id", to_attribute_value(&w.id)?)
                     .key("created_at", to_attribute_value(w.created_at.to_rfc3339())?)
                     .update_expression(
                         "SET #environments = :environments, #updated_at = :updated_at",
                     )
-                    .condition_expression("attribute_exists(#id) and attribute_exists(#created_at)")
+                    .condition_expression("attribute_exists(#id) and attribute_exists(#created_at) and #status <> :cancelled")
                     .expression_attribute_names("#environments", "environments")
                     .expression_attribute_names("#updated_at", "updated_at")
                     .expression_attribute_names("#id", "id")
+                    .expression_attribute_names("#status", "status")
                     .expression_attribute_names("#created_at", "created_at")
                     .expression_attribute_values(":environments", to_attribute_value(environments)?)
+                    .expression_attribute_values(":cancelled", to_attribute_value(Status::Cancelled)?)
+                    .expression_attribute_values(":updated_at", to_attribute_value(Utc::now())?),
+            )
+            .await;
+        let workflow = match workflow {
+            Ok(workflow) => workflow,
+            Err(e) => return Err(self.refused(&w, e).await).context("updating environments"),
+        };
+        events::publish(&workflow);
+        self.record_deployments(&workflow, &previous).await;
+
+        Ok(workflow)
+    }
+
+    /// Marks a running or paused workflow as cancelled, along with its environments, so
+    /// the processor leaves it alone. Returns `None` if the workflow has already finished.
+    pub(crate) async fn cancel(
+        &self,
+        w: Workflow,
+        environments: Vec<Environment>,
+    ) -> Result<Option<Workflow>, anyhow::Error> {
+        let previous = w.environments.clone();
+        let workflow = match self
+            .table
+            .run_update(
+                self.table
+                    .update()
+                    .key("id", to_attribute_value(w.id)?)
+                    .key("created_at", to_attribute_value(w.created_at.to_rfc3339())?)
+                    .update_expression("SET #environments = :environments, #status = :status, #updated_at = :updated_at")
+                    .condition_expression("attribute_exists(#id) and #status IN (:running, :paused)")
+                    .expression_attribute_names("#environments", "environments")
+                    .expression_attribute_names("#status", "status")
+                    .expression_attribute_names("#updated_at", "updated_at")
+                    .expression_attribute_names("#id", "id")
+                    .expression_attribute_values(":environments", to_attribute_value(environments)?)
+                    .expression_attribute_values(":status", to_attribute_value(Status::Cancelled)?)
+                    .expression_attribute_values(":running", to_attribute_value(Status::Running)?)
+                    .expression_attribute_values(":paused", to_attribute_value(Status::Paused)?)
+                    .expression_attribute_values(":updated_at", to_attribute_value(Utc::now())?),
+            )
+            .await
+        {
+            Ok(workflow) => workflow,
+            Err(DynamodbError::NotFound()) => return Ok(None),
+            Err(e) => return Err(e).context("cancelling workflow"),
+        };
+        events::publish(&workflow);
+        self.record_deployments(&workflow, &previous).await;
+
+        Ok(Some(workflow))
+    }
+
+    /// Puts a failed workflow back to running with the given environments, so the
+    /// processor picks it up again straight away. Returns `None` if the workflow hasn't
+    /// failed.
+    pub(crate) async fn retry(
+        &self,
+        w: Workflow,
+        environments: Vec<Environment>,
+    ) -> Result<Option<Workflow>, anyhow::Error> {
+        let workflow = match self
+            .table
+            .run_update(
+                self.table
+                    .update()
+                    .key("id", to_attribute_value(w.id)?)
+                    .key("created_at", to_attribute_value(w.created_at.to_rfc3339())?)
+                    .update_expression("SET #environments = :environments, #status = :status, #due_to_run = :due_to_run, #updated_at = :updated_at")
+                    .condition_expression("attribute_exists(#id) and #status = :failure")
+                    .expression_attribute_names("#environments", "environments")
+                    .expression_attribute_names("#status", "status")
+                    .expression_attribute_names("#due_to_run", "due_to_run")
+                    .expression_attribute_names("#updated_at", "updated_at")
+                    .expression_attribute_names("#id", "id")
+                    .expression_attribute_values(":environments", to_attribute_value(environments)?)
+                    .expression_attribute_values(":status", to_attribute_value(Status::Running)?)
+                    .expression_attribute_values(":failure", to_attribute_value(Status::Failure)?)
+                    .expression_attribute_values(":due_to_run", to_attribute_value(Utc::now())?)
+                    .expression_attribute_values(":updated_at", to_attribute_value(Utc::now())?),
+            )
+            .await
+        {
+            Ok(workflow) => workflow,
+            Err(DynamodbError::NotFound()) => return Ok(None),
+            Err(e) => return Err(e).context("retrying workflow"),
+        };
+        events::publish(&workflow);
+
+        Ok(Some(workflow))
+    }
+
+    /// Starts a workflow that was waiting for approval, recording who approved it.
+    /// Returns `None` if the workflow isn't waiting for approval.
+    pub(crate) async fn approve(
+        &self,
+        w: Workflow,
+        approval: Approval,
+    ) -> Result<Option<Workflow>, anyhow::Error> {
+        let workflow = match self
+            .table
+            .run_update(
+                self.table
+                    .update()
+                    .key("id", to_attribute_value(w.id)?)
+                    .key("created_at", to_attribute_value(w.created_at.to_rfc3339())?)
+                    .update_expression("SET #status = :status, #approval = :approval, #due_to_run = :due_to_run, #updated_at = :updated_at")
+                    .condition_expression("attribute_exists(#id) and #status = :paused")
+                    .expression_attribute_names("#status", "status")
+                    .expression_attribute_names("#approval", "approval")
+                    .expression_attribute_names("#due_to_run", "due_to_run")
+                    .expression_attribute_names("#updated_at", "updated_at")
+                    .expression_attribute_names("#id", "id")
+                    .expression_attribute_values(":status", to_attribute_value(Status::Running)?)
+                    .expression_attribute_values(":paused", to_attribute_value(Status::Paused)?)
+                    .expression_attribute_values(":approval", to_attribute_value(approval)?)
+                    .expression_attribute_values(":due_to_run", to_attribute_value(Utc::now())?)
+                    .expression_attribute_values(":updated_at", to_attribute_value(Utc::now())?),
+            )
+            .await
+        {
+            Ok(workflow) => workflow,
+            Err(DynamodbError::NotFound()) => return Ok(None),
+            Err(e) => return Err(e).context("approving workflow"),
+        };
+        events::publish(&workflow);
+
+        Ok(Some(workflow))
+    }
+
+    /// Makes a running workflow due to run now, skipping the rest of its stability period,
+    /// and records the override. Returns `None` if the workflow isn't running.
+    pub(crate) async fn promote(
+        &self,
+        w: Workflow,
+        environments: Vec<Environment>,
+        promotion: Override,
+    ) -> Result<Option<Workflow>, anyhow::Error> {
+        let previous = w.environments.clone();
+        let workflow = match self
+            .table
+            .run_update(
+                self.table
+                    .update()
+                    .key("id", to_attribute_value(w.id)?)
+                    .key("created_at", to_attribute_value(w.created_at.to_rfc3339())?)
+                    .update_expression("SET #environments = :environments, #due_to_run = :due_to_run, #updated_at = :updated_at, #overrides = list_append(if_not_exists(#overrides, :empty), :override)")
+                    .condition_expression("attribute_exists(#id) and #status = :running")
+                    .expression_attribute_names("#environments", "environments")
+                    .expression_attribute_names("#due_to_run", "due_to_run")
+                    .expression_attribute_names("#updated_at", "updated_at")
+                    .expression_attribute_names("#overrides", "overrides")
+                    .expression_attribute_names("#status", "status")
+                    .expression_attribute_names("#id", "id")
+                    .expression_attribute_values(":environments", to_attribute_value(environments)?)
+                    .expression_attribute_values(":due_to_run", to_attribute_value(Utc::now())?)
+                    .expression_attribute_values(":updated_at", to_attribute_value(Utc::now())?)
+                    .expression_attribute_values(":override", to_attribute_value(vec![promotion])?)
+                    .expression_attribute_values(":empty", to_attribute_value(Vec::<Override>::new())?)
+                    .expression_attribute_values(":running", to_attribute_value(Status::Running)?),
+            )
+            .await
+        {
+            Ok(workflow) => workflow,
+            Err(DynamodbError::NotFound()) => return Ok(None),
+            Err(e) => return Err(e).context("promoting workflow"),
+        };
+        events::publish(&workflow);
+        self.record_deployments(&workflow, &previous).await;
+
+        Ok(Some(workflow))
+    }
+
+    /// Records the state of the workflow's required checks, along with the environments
+    /// and status they lead to, and when to look at the workflow again.
+    pub(crate) async fn update_required_checks(
+        &self,
+        w: Workflow,
+        checks: RequiredChecks,
+        environments: Vec<Environment>,
+        status: Status,
+        due_to_run: DateTime<Utc>,
+    ) -> Result<Workflow, anyhow::Error> {
+        let workflow = self
+            .table
+            .run_update(
+                self.table
+                    .update()
+                    .key("id", to_attribute_value(&w.id)?)
+                    .key("created_at", to_attribute_value(w.created_at.to_rfc3339())?)
+                    .update_expression("SET #required_checks = :required_checks, #environments = :environments, #status = :status, #due_to_run = :due_to_run, #updated_at = :updated_at")
+                    .condition_expression("attribute_exists(#id) and #status = :running")
+                    .expression_attribute_names("#required_checks", "required_checks")
+                    .expression_attribute_names("#environments", "environments")
+                    .expression_attribute_names("#status", "status")
+                    .expression_attribute_names("#due_to_run", "due_to_run")
+                    .expression_attribute_names("#updated_at", "updated_at")
+                    .expression_attribute_names("#id", "id")
+                    .expression_attribute_values(":required_checks", to_attribute_value(checks)?)
+                    .expression_attribute_values(":environments", to_attribute_value(environments)?)
+                    .expression_attribute_values(":status", to_attribute_value(status)?)
+                    .expression_attribute_values(":running", to_attribute_value(Status::Running)?)
+                    .expression_attribute_values(":due_to_run", to_attribute_value(due_to_run)?)
                     .expression_attribute_values(":updated_at", to_attribute_value(Utc::now())?),
             )
+            .await;
+        let workflow = match workflow {
+            Ok(workflow) => workflow,
+            Err(e) => return Err(self.refused(&w, e).await).context("updating required checks"),
+        };
+        events::publish(&workflow);
+
+        Ok(workflow)
+    }
+
+    /// Explains why an update to the workflow, conditional on it still running, was
+    /// refused: [`Cancelled`] if it has been cancelled since, otherwise the error as is.
+    async fn refused(&self, w: &Workflow, e: DynamodbError) -> anyhow::Error {
+        if let DynamodbError::NotFound() = e {
+            let current = self.get(&w.owner, &w.repo, w.created_at.to_dt()).await;
+            if let Ok(Some(current)) = current {
+                if current.status == Status::Cancelled {
+                    return Cancelled.into();
+                }
+            }
+        }
+        e.into()
+    }
+
+    /// Stores a deployment for every environment of the workflow that has started, and
+    /// changed since `previous`.
+    async fn record_deployments(&self, workflow: &Workflow, previous: &[Environment]) {
+        for env in &workflow.environments {
+            let Some(started_at) = env.started_at else {
+                continue;
+            };
+            if previous.contains(env) {
+                continue;
+            }
+
+            let deployment = Deployment {
+                id: format!("{}/{}/{}", workflow.owner, workflow.repo, env.name),
+                started_at: CreatedAt(started_at),
+                finished_at: env.finished_at,
+                status: env.status,
+                owner: workflow.owner.clone(),
+                repo: workflow.repo.clone(),
+                environment: env.name.clone(),
+                sha: workflow.sha.clone(),
+                git_ref: workflow.git_ref.clone(),
+                commit_message: workflow.commit_message.clone(),
+                workflow_created_at: workflow.created_at.clone(),
+            };
+            if let Err(e) = self.deployments.put_item(deployment).await {
+                log::error!("failed to record deployment: {:#}", e);
+            }
+        }
+    }
+
+    /// Lists a page of deployments to an environment, newest first, optionally only
+    /// those started between `from` and `to`.
+    pub async fn list_deployments(
+        &self,
+        owner: &str,
+        repo: &str,
+        environment: &str,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+        cursor: Option<String>,
+    ) -> Result<DeploymentPage, anyhow::Error> {
+        let mut query = self
+            .deployments
+            .query()
+            .scan_index_forward(false)
+            .expression_attribute_names("#id", "id")
+            .expression_attribute_values(
+                ":id",
+                to_attribute_value(format!("{owner}/{repo}/{environment}"))?,
+            );
+
+        let key_condition = match (from, to) {
+            (Some(_), Some(_)) => "#id = :id and #started_at between :from and :to",
+            (Some(_), None) => "#id = :id and #started_at >= :from",
+            (None, Some(_)) => "#id = :id and #started_at <= :to",
+            (None, None) => "#id = :id",
+        };
+        if from.is_some() || to.is_some() {
+            query = query.expression_attribute_names("#started_at", "started_at");
+        }
+        if let Some(from) = from {
+            query =
+                query.expression_attribute_values(":from", to_attribute_value(CreatedAt(from))?);
+        }
+        if let Some(to) = to {
+            query = query.expression_attribute_values(":to", to_attribute_value(CreatedAt(to))?);
+        }
+
+        let (deployments, cursor) = self
+            .deployments
+            .run_query_page(
+                query.key_condition_expression(key_condition),
+                cursor,
+                DEPLOYMENTS_PAGE_SIZE,
+            )
             .await
-            .context("updating environments")
+            .context("list deployments")?;
+
+        Ok(DeploymentPage {
+            deployments,
+            cursor,
+        })
     }
 }
 
@@ -0,0 +1,229 @@
+//! A repository can describe its pipeline in a `.pipedream.toml` at its root, read at
+//! the sha being deployed, rather than passing it to `/api/workflow` from every CI job:
+//!
+//! ```toml
+//! # Minutes to wait after each environment before starting the next.
+//! stability_period_minutes = 30
+//! # Hold every workflow until someone approves it on the dashboard.
+//! require_approval = false
+//!
+//! [[environments]]
+//! name = "staging"
+//! # Overrides the stability period after this environment.
+//! stability_period_minutes = 10
+//! # Where the environment can be reached, linked from its GitHub deployments.
+//! url = "https://staging.example.com"
+//!
+//! [[environments]]
+//! name = "production"
+//!
+//! [policies]
+//! # Environments that can't be skipped when promoting a workflow.
+//! protected_environments = ["production"]
+//!
+//! # Used when the repository has no branch rules of its own in pipedream's settings.
+//! [[policies.branches]]
+//! pattern = "main"
+//! environments = ["staging", "production"]
+//!
+//! # Creates workflows from GitHub webhooks, without a CI step calling the API.
+//! [trigger]
+//! branches = ["main", "release/*"]
+//! # "push" to deploy every push, or "check_suite" or "workflow_run" to wait for CI to
+//! # pass. Defaults to "push".
+//! on = "workflow_run"
+//! # With "workflow_run", the GitHub Actions workflow that must pass. Any if unset.
+//! workflow = "CI"
+//! ```
+//!
+//! Anything passed to `/api/workflow` takes precedence over the file.
+
+use crate::settings::{glob_matches, BranchRule};
+use serde::{Deserialize, Serialize};
+
+/// Where the pipeline configuration lives in a repository.
+pub const CONFIG_PATH: &str = ".pipedream.toml";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct PipelineConfig {
+    #[serde(default)]
+    pub stability_period_minutes: Option<usize>,
+    #[serde(default)]
+    pub require_approval: bool,
+    #[serde(default)]
+    pub environments: Vec<EnvironmentConfig>,
+    #[serde(default)]
+    pub policies: Policies,
+    #[serde(default)]
+    pub trigger: Option<Trigger>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct EnvironmentConfig {
+    pub name: String,
+    #[serde(default)]
+    pub stability_period_minutes: Option<usize>,
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Policies {
+    #[serde(default)]
+    pub protected_environments: Vec<String>,
+    #[serde(default)]
+    pub branches: Vec<BranchRule>,
+}
+
+/// Which GitHub webhooks create workflows for the repository.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Trigger {
+    /// Branches whose commits are deployed, as patterns like `main` or `release/*`.
+    pub branches: Vec<String>,
+    #[serde(default)]
+    pub on: TriggerEvent,
+    /// The GitHub Actions workflow that must pass, for `workflow_run`.
+    #[serde(default)]
+    pub workflow: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerEvent {
+    #[default]
+    Push,
+    CheckSuite,
+    WorkflowRun,
+}
+
+impl Trigger {
+    /// Whether a webhook event for the branch should create a workflow. `workflow` is the
+    /// name of the GitHub Actions workflow, for `workflow_run` events.
+    pub fn matches(&self, event: TriggerEvent, branch: &str, workflow: Option<&str>) -> bool {
+        self.on == event
+            && self.branches.iter().any(|b| glob_matches(b, branch))
+            && (event != TriggerEvent::WorkflowRun
+                || self.workflow.is_none()
+                || self.workflow.as_deref() == workflow)
+    }
+}
+
+impl PipelineConfig {
+    /// Parses and checks a `.pipedream.toml`, with an error fit to show whoever wrote it.
+    #[cfg(feature = "ssr")]
+    pub fn parse(text: &str) -> Result<PipelineConfig, String> {
+        let config: PipelineConfig = toml::from_str(text).map_err(|e| e.to_string())?;
+
+        let mut names: Vec<&str> = vec![];
+        for e in &config.environments {
+            if e.name.trim().is_empty() {
+                return Err("environment names can't be empty".to_string());
+            }
+            if names.contains(&e.name.as_str()) {
+                return Err(format!("environment {} is listed more than once", e.name));
+            }
+            names.push(&e.name);
+        }
+
+        Ok(config)
+    }
+
+    pub fn environment_names(&self) -> Vec<String> {
+        self.environments.iter().map(|e| e.name.clone()).collect()
+    }
+
+    /// The stability period after the named environment, if the file sets one for it.
+    pub fn stability_period_after(&self, environment: &str) -> Option<usize> {
+        self.environments
+            .iter()
+            .find(|e| e.name == environment)
+            .and_then(|e| e.stability_period_minutes)
+    }
+
+    /// Where the named environment can be reached, if the file says.
+    pub fn environment_url(&self, environment: &str) -> Option<&str> {
+        self.environments
+            .iter()
+            .find(|e| e.name == environment)
+            .and_then(|e| e.url.as_deref())
+    }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let config = PipelineConfig::parse(
+            r#"
+            stability_period_minutes = 30
+
+            [[environments]]
+            name = "staging"
+            stability_period_minutes = 10
+
+            [[environments]]
+            name = "production"
+            url = "https://example.com"
+
+            [policies]
+            protected_environments = ["production"]
+
+            [[policies.branches]]
+            pattern = "main"
+            environments = ["staging", "production"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.stability_period_minutes, Some(30));
+        assert_eq!(config.environment_names(), vec!["staging", "production"]);
+        assert_eq!(config.stability_period_after("staging"), Some(10));
+        assert_eq!(config.stability_period_after("production"), None);
+        assert_eq!(config.environment_url("staging"), None);
+        assert_eq!(
+            config.environment_url("production"),
+            Some("https://example.com")
+        );
+        assert_eq!(config.policies.branches[0].pattern, "main");
+
+        assert_eq!(config.trigger, None);
+
+        assert_eq!(PipelineConfig::parse(""), Ok(PipelineConfig::default()));
+        assert!(PipelineConfig::parse("stability_period = 30").is_err());
+        assert!(PipelineConfig::parse(
+            "[[environments]]\nname = \"a\"\n[[environments]]\nname = \"a\""
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_trigger_matches() {
+        let config = PipelineConfig::parse(
+            r#"
+            [trigger]
+            branches = ["main", "release/*"]
+            on = "workflow_run"
+            workflow = "CI"
+            "#,
+        )
+        .unwrap();
+        let trigger = config.trigger.unwrap();
+
+        assert!(trigger.matches(TriggerEvent::WorkflowRun, "main", Some("CI")));
+        assert!(trigger.matches(TriggerEvent::WorkflowRun, "release/1.2", Some("CI")));
+        assert!(!trigger.matches(TriggerEvent::WorkflowRun, "main", Some("Lint")));
+        assert!(!trigger.matches(TriggerEvent::WorkflowRun, "feature/login", Some("CI")));
+        assert!(!trigger.matches(TriggerEvent::Push, "main", None));
+
+        let push = PipelineConfig::parse("[trigger]\nbranches = [\"main\"]")
+            .unwrap()
+            .trigger
+            .unwrap();
+        assert!(push.matches(TriggerEvent::Push, "main", None));
+    }
+}
@@ -0,0 +1,109 @@
+use super::config::CONFIG_PATH;
+use super::{directives, CreateWorkflowRequest, Directive, PipelineConfig, Provenance, Workflow};
+use crate::github;
+use crate::settings::{self, resolve_pipeline};
+
+/// A commit to deploy, as asked for by the API or a webhook, before the repository's
+/// configuration and policies are applied.
+pub struct NewWorkflow {
+    pub owner: String,
+    pub repo: String,
+    pub git_ref: String,
+    pub sha: String,
+    pub commit_message: String,
+    /// Labels of the pull request the commit came from, if known.
+    pub labels: Vec<String>,
+    /// The pipeline asked for, or empty to leave it to the repository's configuration.
+    pub environments: Vec<String>,
+    pub stability_period_minutes: Option<usize>,
+    pub provenance: Option<Provenance>,
+    /// Whether to refuse a second workflow for the sha, for webhooks GitHub may deliver
+    /// more than once.
+    pub once_per_sha: bool,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum CreateError {
+    #[error("invalid {CONFIG_PATH}: {0}")]
+    InvalidConfig(String),
+    #[error("{0}")]
+    Invalid(String),
+    #[error("{0}")]
+    NotAllowed(String),
+    #[error("{0}")]
+    AlreadyExists(String),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+/// Gets the repository's pipeline configuration at the sha, if it has one.
+pub async fn fetch_config(
+    owner: &str,
+    repo: &str,
+    sha: &str,
+) -> Result<Option<PipelineConfig>, CreateError> {
+    github::get_file_contents(owner, repo, CONFIG_PATH, sha)
+        .await?
+        .map(|text| PipelineConfig::parse(&text))
+        .transpose()
+        .map_err(CreateError::InvalidConfig)
+}
+
+/// Creates a workflow for the commit, filling in what wasn't asked for from the
+/// repository's configuration, then applying its branch rules and directives.
+pub async fn create(
+    new: NewWorkflow,
+    config: Option<PipelineConfig>,
+) -> Result<Workflow, CreateError> {
+    let settings = settings::client().await.get(&new.owner, &new.repo).await?;
+
+    let Some(stability_period_minutes) = new
+        .stability_period_minutes
+        .or(config.as_ref().and_then(|c| c.stability_period_minutes))
+    else {
+        return Err(CreateError::Invalid(format!(
+            "stability_period_minutes is required unless set in {CONFIG_PATH}"
+        )));
+    };
+    let environments = match (new.environments.is_empty(), &config) {
+        (true, Some(config)) => config.environment_names(),
+        _ => new.environments,
+    };
+    // Rules set in pipedream take precedence over those in the repository.
+    let rules = match &config {
+        Some(config) if settings.branch_rules.is_empty() => &config.policies.branches,
+        _ => &settings.branch_rules,
+    };
+    let environments =
+        resolve_pipeline(rules, &new.git_ref, environments).map_err(CreateError::NotAllowed)?;
+    let directives = directives::parse(&new.commit_message, &new.labels);
+
+    let mut request = CreateWorkflowRequest {
+        git_ref: new.git_ref,
+        owner: new.owner,
+        repo: new.repo,
+        sha: new.sha,
+        stability_period_minutes,
+        max_stability_period_minutes: None,
+        environments,
+        commit_message: new.commit_message,
+        provenance: new.provenance,
+        rollback_of: None,
+        directives: vec![],
+        config,
+        once_per_sha: new.once_per_sha,
+    };
+    request.apply_directives(directives, &settings.ignored_directives);
+    if request.environments.is_empty() && !request.directives.contains(&Directive::SkipDeploy) {
+        return Err(CreateError::Invalid(
+            "no environments to deploy to".to_string(),
+        ));
+    }
+
+    let sha = request.sha.clone();
+    super::client()
+        .await
+        .create(request)
+        .await?
+        .ok_or_else(|| CreateError::AlreadyExists(format!("{sha} already has a workflow")))
+}
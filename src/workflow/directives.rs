@@ -0,0 +1,260 @@
+//! Directives let a commit message, or the labels of the pull request it came from,
+//! change how the workflow for that commit runs.
+//!
+//! In a commit message a directive is written in square brackets, anywhere in the
+//! message:
+//!
+//! ```text
+//! directive   = "[" name [ " " environments ] "]"
+//! name        = "skip deploy" | "hotfix" | "only" | "require approval"
+//! environments = environment { "," environment }
+//! ```
+//!
+//! A pull request label is a directive without the brackets, e.g. `hotfix` or
+//! `only staging`. Names are case insensitive and surrounding whitespace is ignored.
+//! Bracketed text that isn't a directive, like `[WIP]`, is left alone.
+//!
+//! - `[skip deploy]` creates the workflow without deploying to any environment.
+//! - `[hotfix]` shortens the stability period to `HOTFIX_STABILITY_PERIOD_MINUTES`.
+//! - `[only staging,production]` deploys to just the named environments of the pipeline.
+//! - `[require approval]` holds the workflow until someone approves it on the dashboard.
+
+use super::config::PipelineConfig;
+#[cfg(feature = "ssr")]
+use super::CreateWorkflowRequest;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// The stability period of a workflow created with the `[hotfix]` directive, if the
+/// requested one is longer.
+pub const HOTFIX_STABILITY_PERIOD_MINUTES: usize = 5;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Directive {
+    SkipDeploy,
+    Hotfix,
+    Only(Vec<String>),
+    RequireApproval,
+}
+
+/// Which directive it is, regardless of its arguments, for repositories to choose which
+/// directives they honour.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum DirectiveKind {
+    SkipDeploy,
+    Hotfix,
+    Only,
+    RequireApproval,
+}
+
+impl DirectiveKind {
+    pub fn all() -> Vec<DirectiveKind> {
+        vec![
+            DirectiveKind::SkipDeploy,
+            DirectiveKind::Hotfix,
+            DirectiveKind::Only,
+            DirectiveKind::RequireApproval,
+        ]
+    }
+}
+
+impl Display for DirectiveKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            DirectiveKind::SkipDeploy => "skip deploy",
+            DirectiveKind::Hotfix => "hotfix",
+            DirectiveKind::Only => "only",
+            DirectiveKind::RequireApproval => "require approval",
+        })
+    }
+}
+
+impl Directive {
+    pub fn kind(&self) -> DirectiveKind {
+        match self {
+            Directive::SkipDeploy => DirectiveKind::SkipDeploy,
+            Directive::Hotfix => DirectiveKind::Hotfix,
+            Directive::Only(_) => DirectiveKind::Only,
+            Directive::RequireApproval => DirectiveKind::RequireApproval,
+        }
+    }
+
+    /// Parses a single directive, without its brackets.
+    fn parse(s: &str) -> Option<Directive> {
+        let s = s.trim();
+        match s.to_lowercase().as_str() {
+            "skip deploy" => Some(Directive::SkipDeploy),
+            "hotfix" => Some(Directive::Hotfix),
+            "require approval" => Some(Directive::RequireApproval),
+            _ => {
+                // Environment names keep their case, as they must match the pipeline's.
+                s.get(..5).filter(|p| p.eq_ignore_ascii_case("only "))?;
+                let environments = s[5..]
+                    .split(',')
+                    .map(|e| e.trim().to_string())
+                    .filter(|e| !e.is_empty())
+                    .collect::<Vec<_>>();
+                (!environments.is_empty()).then_some(Directive::Only(environments))
+            }
+        }
+    }
+}
+
+impl Display for Directive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Directive::Only(environments) => write!(f, "[only {}]", environments.join(",")),
+            d => write!(f, "[{}]", d.kind()),
+        }
+    }
+}
+
+/// Finds the directives in a commit message and pull request labels, each at most once,
+/// with the first `[only ...]` winning.
+pub fn parse(commit_message: &str, labels: &[String]) -> Vec<Directive> {
+    let mut found = vec![];
+    let mut rest = commit_message;
+    while let Some(start) = rest.find('[') {
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find(']') else {
+            break;
+        };
+        found.extend(Directive::parse(&rest[..end]));
+        rest = &rest[end + 1..];
+    }
+    found.extend(labels.iter().filter_map(|l| Directive::parse(l)));
+
+    let mut directives: Vec<Directive> = vec![];
+    for d in found {
+        if !directives.iter().any(|x| x.kind() == d.kind()) {
+            directives.push(d);
+        }
+    }
+    directives
+}
+
+#[cfg(feature = "ssr")]
+impl CreateWorkflowRequest {
+    /// Adjusts the request by the directives the repository honours, recording those
+    /// that changed it. An `[only ...]` that names none of the pipeline's environments
+    /// is ignored, rather than deploying nothing.
+    pub fn apply_directives(&mut self, directives: Vec<Directive>, ignored: &[DirectiveKind]) {
+        for d in directives {
+            if ignored.contains(&d.kind()) {
+                continue;
+            }
+            match &d {
+                Directive::SkipDeploy => self.environments.clear(),
+                Directive::Hotfix => {
+                    self.stability_period_minutes = self
+                        .stability_period_minutes
+                        .min(HOTFIX_STABILITY_PERIOD_MINUTES);
+                    // Also caps the periods `.pipedream.toml` sets per environment.
+                    self.max_stability_period_minutes = Some(
+                        self.max_stability_period_minutes
+                            .unwrap_or(usize::MAX)
+                            .min(HOTFIX_STABILITY_PERIOD_MINUTES),
+                    );
+                }
+                Directive::Only(only) => {
+                    if !self.environments.iter().any(|e| only.contains(e)) {
+                        continue;
+                    }
+                    self.environments.retain(|e| only.contains(e));
+                }
+                Directive::RequireApproval => {}
+            }
+            self.directives.push(d);
+        }
+    }
+
+    pub fn requires_approval(&self) -> bool {
+        requires_approval(&self.directives, self.config.as_ref())
+    }
+}
+
+/// Whether a workflow has to be approved before it deploys, because its commit asked for
+/// it with `[require approval]` or the repository's configuration always does.
+pub fn requires_approval(directives: &[Directive], config: Option<&PipelineConfig>) -> bool {
+    directives.contains(&Directive::RequireApproval) || config.is_some_and(|c| c.require_approval)
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "ssr")]
+    use super::super::fixtures::request;
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse(
+                "[WIP] fix login [Hotfix] [Only staging, Production]\n\n[require approval]",
+                &[]
+            ),
+            vec![
+                Directive::Hotfix,
+                Directive::Only(vec!["staging".to_string(), "Production".to_string()]),
+                Directive::RequireApproval,
+            ]
+        );
+        assert_eq!(
+            parse("update readme [skip deploy] [skip deploy]", &[]),
+            vec![Directive::SkipDeploy]
+        );
+        assert_eq!(
+            parse(
+                "fix [only] [unclosed",
+                &["hotfix".to_string(), "bug".to_string()]
+            ),
+            vec![Directive::Hotfix]
+        );
+        assert_eq!(parse("no directives here", &[]), vec![]);
+    }
+
+    #[cfg(feature = "ssr")]
+    #[test]
+    fn test_apply_directives() {
+        let mut r = request(&["dev", "staging", "production"]);
+        r.apply_directives(
+            vec![
+                Directive::Hotfix,
+                Directive::Only(vec!["production".to_string(), "staging".to_string()]),
+                Directive::RequireApproval,
+            ],
+            &[DirectiveKind::RequireApproval],
+        );
+        assert_eq!(r.stability_period_minutes, HOTFIX_STABILITY_PERIOD_MINUTES);
+        assert_eq!(
+            r.max_stability_period_minutes,
+            Some(HOTFIX_STABILITY_PERIOD_MINUTES)
+        );
+        assert_eq!(r.environments, vec!["staging", "production"]);
+        assert!(!r.requires_approval());
+        assert_eq!(r.directives.len(), 2);
+
+        let mut r = request(&["dev", "staging", "production"]);
+        r.apply_directives(vec![Directive::Only(vec!["stagin".to_string()])], &[]);
+        assert_eq!(
+            r.environments,
+            request(&["dev", "staging", "production"]).environments
+        );
+        assert!(r.directives.is_empty());
+
+        let mut r = request(&["dev", "staging", "production"]);
+        r.apply_directives(vec![Directive::SkipDeploy], &[]);
+        assert!(r.environments.is_empty());
+    }
+
+    #[test]
+    fn test_display() {
+        for d in [
+            Directive::SkipDeploy,
+            Directive::Hotfix,
+            Directive::Only(vec!["staging".to_string(), "production".to_string()]),
+            Directive::RequireApproval,
+        ] {
+            assert_eq!(parse(&d.to_string(), &[]), vec![d]);
+        }
+    }
+}
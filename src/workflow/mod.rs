@@ -3,9 +3,33 @@ use std::fmt::Display;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "ssr")]
+mod cancel;
+
+#[cfg(feature = "ssr")]
+pub use cancel::cancel;
+
+#[cfg(feature = "ssr")]
+mod create;
+
+#[cfg(feature = "ssr")]
+pub use create::{create, fetch_config, CreateError, NewWorkflow};
+
+#[cfg(feature = "ssr")]
+mod check_run;
+
+#[cfg(feature = "ssr")]
+mod checks;
+
 #[cfg(feature = "ssr")]
 mod client;
 
+pub mod config;
+pub mod directives;
+
+pub use config::PipelineConfig;
+pub use directives::{Directive, DirectiveKind};
+
 #[cfg(feature = "ssr")]
 pub use client::*;
 
@@ -21,6 +45,19 @@ pub enum Status {
     Running,
     Success,
     Failure,
+    Cancelled,
+}
+
+impl Status {
+    pub fn all() -> Vec<Status> {
+        vec![
+            Status::Running,
+            Status::Success,
+            Status::Failure,
+            Status::Paused,
+            Status::Cancelled,
+        ]
+    }
 }
 
 impl Display for Status {
@@ -30,6 +67,7 @@ impl Display for Status {
             Status::Running => "Running",
             Status::Success => "Success",
             Status::Failure => "Failure",
+            Status::Cancelled => "Cancelled",
         })
     }
 }
@@ -41,13 +79,19 @@ pub enum EnvironmentStatus {
     Running,
     Success,
     Queued,
+    Cancelled,
+    /// Skipped over when the workflow was promoted.
+    Skipped,
 }
 
 impl EnvironmentStatus {
     pub fn is_terminal(&self) -> bool {
         matches!(
             self,
-            EnvironmentStatus::Failure | EnvironmentStatus::Success
+            EnvironmentStatus::Failure
+                | EnvironmentStatus::Success
+                | EnvironmentStatus::Cancelled
+                | EnvironmentStatus::Skipped
         )
     }
 }
@@ -59,11 +103,31 @@ impl From<EnvironmentStatus> for Status {
             EnvironmentStatus::Running => Status::Running,
             EnvironmentStatus::Queued => Status::Running,
             EnvironmentStatus::Success => Status::Success,
+            EnvironmentStatus::Skipped => Status::Success,
             EnvironmentStatus::Failure => Status::Failure,
+            EnvironmentStatus::Cancelled => Status::Cancelled,
         }
     }
 }
 
+/// A GitHub Actions run triggered by the deployment of an environment.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Run {
+    pub id: u64,
+    pub name: String,
+    pub url: String,
+}
+
+/// An earlier, failed attempt at deploying an environment that has since been retried.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Attempt {
+    pub status: EnvironmentStatus,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub deployment_id: Option<u64>,
+    pub runs: Vec<Run>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Environment {
     pub name: String,
@@ -71,13 +135,95 @@ pub struct Environment {
     pub started_at: Option<DateTime<Utc>>,
     pub finished_at: Option<DateTime<Utc>>,
     pub deployment_id: Option<u64>,
+    #[serde(default)]
+    pub runs: Vec<Run>,
+    /// Previous attempts, oldest first.
+    #[serde(default)]
+    pub attempts: Vec<Attempt>,
+}
+
+impl Environment {
+    /// Resets the environment to be deployed again, keeping the current attempt.
+    pub fn retry(&mut self) {
+        self.attempts.push(Attempt {
+            status: self.status,
+            started_at: self.started_at.take(),
+            finished_at: self.finished_at.take(),
+            deployment_id: self.deployment_id.take(),
+            runs: std::mem::take(&mut self.runs),
+        });
+        self.status = EnvironmentStatus::Pending;
+    }
+
+    /// How long the environment took to deploy, or has taken so far.
+    pub fn duration(&self, now: DateTime<Utc>) -> Option<chrono::Duration> {
+        self.started_at
+            .map(|started_at| self.finished_at.unwrap_or(now) - started_at)
+    }
+}
+
+/// A record of someone promoting a workflow past its stability period, and possibly
+/// some of its environments.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Override {
+    pub by: String,
+    pub reason: String,
+    pub at: DateTime<Utc>,
+    /// The environments that were skipped, if any.
+    pub skipped: Vec<String>,
+}
+
+impl Override {
+    /// Describes the override for display, e.g. `Promoted by alice: hotfix (skipped staging)`.
+    pub fn describe(&self) -> String {
+        let mut description = format!("Promoted by {}: {}", self.by, self.reason);
+        if !self.skipped.is_empty() {
+            description.push_str(&format!(" (skipped {})", self.skipped.join(", ")));
+        }
+        description
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Approval {
+    pub by: String,
+    pub at: DateTime<Utc>,
+}
+
+/// Where a workflow is with the commit statuses and check runs the repository requires
+/// to pass before it deploys its first environment.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RequiredChecks {
+    pub contexts: Vec<String>,
+    /// When the workflow started waiting on them.
+    pub since: DateTime<Utc>,
+    /// Contexts still running or not yet reported.
+    pub waiting: Vec<String>,
+    pub failed: Vec<String>,
+}
+
+impl RequiredChecks {
+    pub fn passed(&self) -> bool {
+        self.waiting.is_empty() && self.failed.is_empty()
+    }
+
+    /// Describes the checks for display, e.g. `Waiting for checks: test, lint`.
+    pub fn describe(&self) -> String {
+        if !self.failed.is_empty() {
+            format!("Failed checks: {}", self.failed.join(", "))
+        } else if !self.waiting.is_empty() {
+            format!("Waiting for checks: {}", self.waiting.join(", "))
+        } else {
+            format!("Checks passed: {}", self.contexts.join(", "))
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
 pub struct CreatedAt(DateTime<Utc>);
 
 impl CreatedAt {
-    fn to_rfc3339(&self) -> String {
+    pub(crate) fn to_rfc3339(&self) -> String {
         self.0.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
     }
 
@@ -97,6 +243,36 @@ impl Serialize for CreatedAt {
     }
 }
 
+/// Provenance records where a workflow came from, as asserted by the claims of the
+/// GitHub Actions OIDC token used to create it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Provenance {
+    pub actor: String,
+    pub repository: String,
+    pub run_id: String,
+    pub workflow_ref: String,
+    pub event_name: String,
+    pub git_ref: String,
+}
+
+impl Provenance {
+    pub fn run_url(&self) -> String {
+        format!(
+            "https://github.com/{}/actions/runs/{}",
+            self.repository, self.run_id
+        )
+    }
+
+    /// Whether the workflow was triggered by a push to the given branch, e.g. `main`.
+    pub fn is_push_to(&self, branch: &str) -> bool {
+        self.event_name == "push" && self.git_ref == format!("refs/heads/{branch}")
+    }
+}
+
+/// How long a workflow's current environment can go without finishing before the
+/// workflow is considered stuck.
+pub const STUCK_AFTER_MINUTES: i64 = 30;
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Workflow {
     pub id: String,
@@ -107,13 +283,190 @@ pub struct Workflow {
     pub repo: String,
     pub sha: String,
     pub stability_period_minutes: usize,
+    /// The longest the stability period after any environment can be, whatever the
+    /// configuration says, when a hotfix or rollback shortened it.
+    #[serde(default)]
+    pub max_stability_period_minutes: Option<usize>,
     pub environments: Vec<Environment>,
     pub status: Status,
     pub commit_message: String,
     pub due_to_run: DateTime<Utc>,
+    #[serde(default)]
+    pub provenance: Option<Provenance>,
+    /// When the workflow redeploys an earlier one, when that one was created.
+    #[serde(default)]
+    pub rollback_of: Option<CreatedAt>,
+    #[serde(default)]
+    pub overrides: Vec<Override>,
+    /// The directives from the commit message or labels that shaped the workflow.
+    #[serde(default)]
+    pub directives: Vec<Directive>,
+    /// Who let the workflow start, if it required approval.
+    #[serde(default)]
+    pub approval: Option<Approval>,
+    /// The repository's `.pipedream.toml` at the workflow's sha, if it has one.
+    #[serde(default)]
+    pub config: Option<PipelineConfig>,
+    #[serde(default)]
+    pub required_checks: Option<RequiredChecks>,
+    /// The pipedream check run on the workflow's commit, once created.
+    #[serde(default)]
+    pub check_run_id: Option<u64>,
 }
 
 impl Workflow {
+    /// The path of the workflow's page on the dashboard.
+    pub fn path(&self) -> String {
+        format!(
+            "/dashboard/{}/{}/{}",
+            self.owner,
+            self.repo,
+            self.created_at.to_dt().timestamp()
+        )
+    }
+
+    #[cfg(feature = "ssr")]
+    pub fn url(&self) -> String {
+        format!("{}{}", crate::base_url(), self.path())
+    }
+
+    /// The path of the page of the workflow this one rolled back to, if it is a rollback.
+    pub fn rollback_of_path(&self) -> Option<String> {
+        self.rollback_of.as_ref().map(|created_at| {
+            format!(
+                "/dashboard/{}/{}/{}",
+                self.owner,
+                self.repo,
+                created_at.to_dt().timestamp()
+            )
+        })
+    }
+
+    /// When the next environment is due to start, if the workflow is waiting out the
+    /// stability period of the one before it.
+    pub fn stable_at(&self) -> Option<DateTime<Utc>> {
+        if self.status != Status::Running {
+            return None;
+        }
+
+        match self.next_environment() {
+            Some((idx, next)) if idx > 0 && next.status == EnvironmentStatus::Pending => {
+                Some(self.due_to_run)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the workflow has stopped making progress: it is paused, or its current
+    /// environment has been waiting or deploying for longer than `STUCK_AFTER_MINUTES`.
+    pub fn is_stuck(&self, now: DateTime<Utc>) -> bool {
+        let stuck_after = chrono::Duration::minutes(STUCK_AFTER_MINUTES);
+        match self.status {
+            Status::Paused => true,
+            Status::Running => match self.next_environment() {
+                Some((_, env)) => match env.started_at {
+                    Some(started_at) => now - started_at > stuck_after,
+                    None => now - self.due_to_run > stuck_after,
+                },
+                None => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Whether the workflow is being held until someone approves it.
+    pub fn awaiting_approval(&self) -> bool {
+        self.status == Status::Paused
+            && self.approval.is_none()
+            && directives::requires_approval(&self.directives, self.config.as_ref())
+    }
+
+    /// How long to wait after the named environment before starting the next one.
+    pub fn stability_period_after(&self, environment: &str) -> usize {
+        self.config
+            .as_ref()
+            .and_then(|c| c.stability_period_after(environment))
+            .unwrap_or(self.stability_period_minutes)
+            .min(self.max_stability_period_minutes.unwrap_or(usize::MAX))
+    }
+
+    /// Where the named environment can be reached, as configured in `.pipedream.toml`.
+    pub fn environment_url(&self, environment: &str) -> Option<String> {
+        self.config
+            .as_ref()
+            .and_then(|c| c.environment_url(environment))
+            .map(|url| url.to_string())
+    }
+
+    /// Describes the GitHub deployments the workflow creates.
+    pub fn deployment_description(&self) -> String {
+        match (&self.rollback_of, &self.approval) {
+            (Some(_), _) => "Rollback by pipedream".to_string(),
+            (None, Some(approval)) => format!("Approved by {}, created by pipedream", approval.by),
+            (None, None) => "Created by pipedream".to_string(),
+        }
+    }
+
+    /// A status for the GitHub deployment of the named environment, linking back to the
+    /// workflow.
+    #[cfg(feature = "ssr")]
+    pub(crate) fn deployment_status(
+        &self,
+        environment: &str,
+        state: crate::github::DeploymentStatus,
+        description: impl Into<String>,
+    ) -> crate::github::DeploymentStatusUpdate {
+        crate::github::DeploymentStatusUpdate {
+            state,
+            description: description.into(),
+            log_url: self.url(),
+            environment_url: self.environment_url(environment),
+        }
+    }
+
+    /// The environment that failed the workflow, if it can be retried.
+    pub fn failed_environment(&self) -> Option<(usize, &Environment)> {
+        if self.status != Status::Failure {
+            return None;
+        }
+
+        // Failed environments are terminal, so it won't be the next environment to run.
+        self.environments
+            .iter()
+            .enumerate()
+            .find(|(_, e)| e.status == EnvironmentStatus::Failure)
+    }
+
+    /// The environments of the workflow once the given ones have been skipped, ready to
+    /// promote it. Only environments yet to start can be skipped, and never protected ones.
+    pub fn skip_environments(
+        &self,
+        skip: &[String],
+        protected: &[String],
+        now: DateTime<Utc>,
+    ) -> Result<Vec<Environment>, String> {
+        if self.status != Status::Running {
+            return Err("only running workflows can be promoted".to_string());
+        }
+
+        let mut environments = self.environments.clone();
+        for name in skip {
+            if protected.contains(name) {
+                return Err(format!("{name} is protected and can't be skipped"));
+            }
+            match environments.iter_mut().find(|e| e.name == *name) {
+                Some(e) if e.status == EnvironmentStatus::Pending && e.started_at.is_none() => {
+                    e.status = EnvironmentStatus::Skipped;
+                    e.finished_at = Some(now);
+                }
+                Some(_) => return Err(format!("{name} has already started")),
+                None => return Err(format!("{name} is not part of the workflow")),
+            }
+        }
+
+        Ok(environments)
+    }
+
     pub fn next_environment(&self) -> Option<(usize, &Environment)> {
         let idx = self
             .environments
@@ -124,6 +477,96 @@ impl Workflow {
     }
 }
 
+/// Formats a duration for display, e.g. `1h 5m` or `4m 12s`.
+pub fn format_duration(d: chrono::Duration) -> String {
+    let secs = d.num_seconds().max(0);
+    match (secs / 3600, (secs % 3600) / 60, secs % 60) {
+        (0, 0, s) => format!("{s}s"),
+        (0, m, s) => format!("{m}m {s}s"),
+        (h, m, _) => format!("{h}h {m}m"),
+    }
+}
+
+/// Narrows down the workflows listed for a repository. Every field is optional, with
+/// `from` and `to` bounding when the workflow was created.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WorkflowFilter {
+    pub status: Option<Status>,
+    pub git_ref: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// Matches workflows whose commit message contains this, case sensitively.
+    pub search: Option<String>,
+}
+
+impl WorkflowFilter {
+    pub fn matches(&self, workflow: &Workflow) -> bool {
+        let created_at = workflow.created_at.to_dt();
+        self.status.map_or(true, |s| workflow.status == s)
+            && self
+                .git_ref
+                .as_ref()
+                .map_or(true, |r| r.is_empty() || workflow.git_ref == *r)
+            && self.from.map_or(true, |from| created_at >= from)
+            && self.to.map_or(true, |to| created_at <= to)
+            && self
+                .search
+                .as_ref()
+                .map_or(true, |q| workflow.commit_message.contains(q.as_str()))
+    }
+}
+
+/// A page of workflows, newest first, with the cursor to fetch the next page from if
+/// there is one.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WorkflowPage {
+    pub workflows: Vec<Workflow>,
+    pub cursor: Option<String>,
+}
+
+/// A single deployment of a workflow to an environment. Deployments are stored by
+/// environment as well as on their workflow, so an environment's history can be read
+/// without going through every workflow of the repository.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Deployment {
+    /// A composite of owner/repo/environment.
+    pub id: String,
+    pub started_at: CreatedAt,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub status: EnvironmentStatus,
+    pub owner: String,
+    pub repo: String,
+    pub environment: String,
+    pub sha: String,
+    pub git_ref: String,
+    pub commit_message: String,
+    pub workflow_created_at: CreatedAt,
+}
+
+impl Deployment {
+    pub fn duration(&self, now: DateTime<Utc>) -> chrono::Duration {
+        self.finished_at.unwrap_or(now) - self.started_at.to_dt()
+    }
+
+    /// The path of the deploying workflow's page on the dashboard.
+    pub fn workflow_path(&self) -> String {
+        format!(
+            "/dashboard/{}/{}/{}",
+            self.owner,
+            self.repo,
+            self.workflow_created_at.to_dt().timestamp()
+        )
+    }
+}
+
+/// A page of deployments, newest first, with the cursor to fetch the next page from if
+/// there is one.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DeploymentPage {
+    pub deployments: Vec<Deployment>,
+    pub cursor: Option<String>,
+}
+
 #[cfg(feature = "ssr")]
 pub struct CreateWorkflowRequest {
     pub git_ref: String,
@@ -131,6 +574,213 @@ pub struct CreateWorkflowRequest {
     pub repo: String,
     pub sha: String,
     pub stability_period_minutes: usize,
+    pub max_stability_period_minutes: Option<usize>,
     pub environments: Vec<String>,
     pub commit_message: String,
+    pub provenance: Option<Provenance>,
+    pub rollback_of: Option<CreatedAt>,
+    pub directives: Vec<Directive>,
+    pub config: Option<PipelineConfig>,
+    pub once_per_sha: bool,
+}
+
+#[cfg(feature = "ssr")]
+impl CreateWorkflowRequest {
+    /// Redeploys the sha of an earlier workflow to the given environments, in the order
+    /// the original deployed them, or its whole pipeline if none are given.
+    pub fn rollback(original: &Workflow, environments: &[String], skip_stability: bool) -> Self {
+        let environments = original
+            .environments
+            .iter()
+            .map(|e| e.name.clone())
+            .filter(|name| environments.is_empty() || environments.contains(name))
+            .collect();
+
+        CreateWorkflowRequest {
+            git_ref: original.git_ref.clone(),
+            owner: original.owner.clone(),
+            repo: original.repo.clone(),
+            sha: original.sha.clone(),
+            stability_period_minutes: if skip_stability {
+                0
+            } else {
+                original.stability_period_minutes
+            },
+            max_stability_period_minutes: if skip_stability {
+                Some(0)
+            } else {
+                original.max_stability_period_minutes
+            },
+            environments,
+            commit_message: original.commit_message.clone(),
+            provenance: original.provenance.clone(),
+            rollback_of: Some(original.created_at.clone()),
+            directives: vec![],
+            config: original.config.clone(),
+            once_per_sha: false,
+        }
+    }
+}
+
+/// Workflows and requests for tests to start from, so each test only spells out what it
+/// cares about.
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::*;
+
+    /// An environment with the given status, started unless it's still pending.
+    pub(crate) fn environment(name: &str, status: EnvironmentStatus) -> Environment {
+        Environment {
+            name: name.to_string(),
+            status,
+            started_at: (status != EnvironmentStatus::Pending).then(Utc::now),
+            finished_at: None,
+            deployment_id: None,
+            runs: vec![],
+            attempts: vec![],
+        }
+    }
+
+    /// A workflow deploying `abc123` on `owner/repo`, with nothing else set.
+    pub(crate) fn workflow(status: Status, environments: Vec<Environment>) -> Workflow {
+        let now = Utc::now();
+        Workflow {
+            id: "owner/repo".to_string(),
+            created_at: CreatedAt(now),
+            updated_at: None,
+            git_ref: "refs/heads/main".to_string(),
+            owner: "owner".to_string(),
+            repo: "repo".to_string(),
+            sha: "abc123".to_string(),
+            stability_period_minutes: 30,
+            max_stability_period_minutes: None,
+            environments,
+            status,
+            commit_message: "fix".to_string(),
+            due_to_run: now,
+            provenance: None,
+            rollback_of: None,
+            overrides: vec![],
+            directives: vec![],
+            approval: None,
+            config: None,
+            required_checks: None,
+            check_run_id: None,
+        }
+    }
+
+    /// A request to deploy `abc123` on `owner/repo` to the given environments.
+    #[cfg(feature = "ssr")]
+    pub(crate) fn request(environments: &[&str]) -> CreateWorkflowRequest {
+        CreateWorkflowRequest {
+            git_ref: "refs/heads/main".to_string(),
+            owner: "owner".to_string(),
+            repo: "repo".to_string(),
+            sha: "abc123".to_string(),
+            stability_period_minutes: 30,
+            max_stability_period_minutes: None,
+            environments: environments.iter().map(|e| e.to_string()).collect(),
+            commit_message: "fix".to_string(),
+            provenance: None,
+            rollback_of: None,
+            directives: vec![],
+            config: None,
+            once_per_sha: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fixtures::{environment, workflow};
+    use super::*;
+
+    #[test]
+    fn test_failed_environment() {
+        let mut workflow = workflow(
+            Status::Failure,
+            vec![
+                environment("dev", EnvironmentStatus::Success),
+                environment("staging", EnvironmentStatus::Failure),
+                environment("prod", EnvironmentStatus::Pending),
+            ],
+        );
+        assert_eq!(
+            workflow
+                .failed_environment()
+                .map(|(idx, e)| (idx, e.name.as_str())),
+            Some((1, "staging"))
+        );
+
+        let (idx, _) = workflow.failed_environment().unwrap();
+        workflow.environments[idx].retry();
+        workflow.status = Status::Running;
+        assert_eq!(workflow.failed_environment(), None);
+        assert_eq!(workflow.next_environment().map(|(idx, _)| idx), Some(1));
+        assert_eq!(
+            workflow.environments[1].attempts[0].status,
+            EnvironmentStatus::Failure
+        );
+    }
+
+    #[test]
+    fn test_skip_environments() {
+        let workflow = workflow(
+            Status::Running,
+            vec![
+                environment("dev", EnvironmentStatus::Success),
+                environment("staging", EnvironmentStatus::Pending),
+                environment("prod", EnvironmentStatus::Pending),
+            ],
+        );
+        let now = Utc::now();
+
+        let environments = workflow
+            .skip_environments(&["staging".to_string()], &["prod".to_string()], now)
+            .unwrap();
+        assert_eq!(environments[1].status, EnvironmentStatus::Skipped);
+        assert_eq!(environments[2].status, EnvironmentStatus::Pending);
+
+        assert!(workflow
+            .skip_environments(&["prod".to_string()], &["prod".to_string()], now)
+            .is_err());
+        assert!(workflow
+            .skip_environments(&["dev".to_string()], &[], now)
+            .is_err());
+    }
+
+    #[test]
+    fn test_stability_period_after() {
+        let mut workflow = workflow(
+            Status::Running,
+            vec![
+                environment("staging", EnvironmentStatus::Success),
+                environment("production", EnvironmentStatus::Pending),
+            ],
+        );
+        workflow.config = Some(PipelineConfig {
+            environments: vec![config::EnvironmentConfig {
+                name: "staging".to_string(),
+                stability_period_minutes: Some(60),
+                url: None,
+            }],
+            ..Default::default()
+        });
+        assert_eq!(workflow.stability_period_after("staging"), 60);
+        assert_eq!(workflow.stability_period_after("production"), 30);
+
+        // A hotfix caps the configured periods as well as the workflow's own.
+        workflow.max_stability_period_minutes = Some(5);
+        assert_eq!(workflow.stability_period_after("staging"), 5);
+        assert_eq!(workflow.stability_period_after("production"), 5);
+
+        #[cfg(feature = "ssr")]
+        {
+            workflow.max_stability_period_minutes = None;
+            let rollback = CreateWorkflowRequest::rollback(&workflow, &[], true);
+            assert_eq!(rollback.max_stability_period_minutes, Some(0));
+            workflow.max_stability_period_minutes = rollback.max_stability_period_minutes;
+            assert_eq!(workflow.stability_period_after("staging"), 0);
+        }
+    }
 }
@@ -1,23 +1,53 @@
+use std::collections::HashMap;
+
+use crate::blocks;
+use crate::environments;
 use crate::github;
+use crate::settings;
 
-use super::EnvironmentStatus;
+use super::{check_run, checks, Cancelled, EnvironmentStatus, Run};
 use anyhow::Context;
 use chrono::Utc;
 
 pub async fn process_workflows(client: &'static super::Client) -> Result<(), anyhow::Error> {
     let workflows = client.get_due_to_run(Utc::now()).await?;
 
-    let futures: Vec<_> = workflows
-        .into_iter()
-        .map(|w| tokio::spawn(process_workflow(client, w)))
-        .collect();
+    // Leave workflows for paused repositories where they are until they are resumed.
+    let mut blocked = HashMap::new();
+    let mut futures = vec![];
+    for w in workflows {
+        if !blocked.contains_key(&w.id) {
+            let block = blocks::client()
+                .await
+                .get(&w.owner, &w.repo)
+                .await
+                .context("getting block")?;
+            blocked.insert(w.id.clone(), block.is_some());
+        }
+        if blocked[&w.id] {
+            continue;
+        }
+
+        futures.push(tokio::spawn(process_workflow(client, w)));
+    }
 
     for f in futures {
-        f.await??;
+        match f.await? {
+            // Updates to a workflow are conditional on it not having been cancelled, so
+            // one cancelled partway through processing it is left as it is.
+            Err(e) if cancelled_meanwhile(&e) => {
+                log::info!("workflow cancelled while processing it: {:#}", e);
+            }
+            result => result?,
+        }
     }
     Ok(())
 }
 
+fn cancelled_meanwhile(e: &anyhow::Error) -> bool {
+    e.is::<Cancelled>()
+}
+
 impl From<github::WorkflowStatus> for EnvironmentStatus {
     fn from(status: github::WorkflowStatus) -> Self {
         match status {
@@ -47,6 +77,46 @@ fn overall_status(workflows: Vec<github::Workflow>) -> EnvironmentStatus {
         .unwrap_or(EnvironmentStatus::Running)
 }
 
+/// Explains what's holding up or ended the GitHub Actions runs of a deployment, where
+/// their overall status doesn't.
+fn run_state(runs: &[github::Workflow]) -> Option<&'static str> {
+    runs.iter().find_map(|r| match r.status {
+        github::WorkflowStatus::Waiting => Some("awaiting approval"),
+        github::WorkflowStatus::ActionRequired => Some("action required"),
+        github::WorkflowStatus::TimedOut => Some("timed out"),
+        github::WorkflowStatus::Cancelled => Some("run cancelled"),
+        _ => None,
+    })
+}
+
+/// Describes an environment's GitHub deployment, going by the state of its runs and how
+/// long the workflow waits after it, if another environment follows.
+fn describe_deployment(
+    status: EnvironmentStatus,
+    run_state: Option<&str>,
+    soak_minutes: Option<usize>,
+) -> String {
+    match status {
+        EnvironmentStatus::Success => match soak_minutes {
+            Some(0) | None => "deployed".to_string(),
+            Some(m) if m < 60 => format!("deployed, soaking for {m}m"),
+            Some(m) if m % 60 == 0 => format!("deployed, soaking for {}h", m / 60),
+            Some(m) => format!("deployed, soaking for {}h {}m", m / 60, m % 60),
+        },
+        EnvironmentStatus::Failure => run_state.unwrap_or("failed").to_string(),
+        EnvironmentStatus::Queued => run_state.unwrap_or("queued").to_string(),
+        _ => run_state.unwrap_or("deploying").to_string(),
+    }
+}
+
+/// Records the environment as live, which is only used for display so doesn't fail
+/// processing of the workflow.
+async fn record_live(workflow: &super::Workflow, idx: usize) {
+    if let Err(e) = environments::client().await.record(workflow, idx).await {
+        log::error!("failed to record live environment: {:#}", e);
+    }
+}
+
 async fn process_workflow(
     client: &'static super::Client,
     workflow: super::Workflow,
@@ -61,9 +131,15 @@ async fn process_workflow(
                 .rev()
                 .find(|w| w.status.is_terminal());
             let status = w.map(|w| w.status).unwrap_or(EnvironmentStatus::Success);
-            client.mark_workflow_done(workflow, status.into()).await
+            let workflow = client.mark_workflow_done(workflow, status.into()).await?;
+            check_run::sync(&workflow).await;
+            Ok(())
         }
         Some((idx, w)) => {
+            // How long the workflow waits after this environment, if another follows.
+            let soak_minutes = (idx + 1 < workflow.environments.len())
+                .then(|| workflow.stability_period_after(&w.name));
+
             if w.status == EnvironmentStatus::Running {
                 // it's running, we need to check the status of the workflows.
                 let github_workflows = github::list_workflows(
@@ -97,27 +173,66 @@ async fn process_workflow(
                             environment.finished_at = Some(Utc::now());
                         }
                         let next_due_to_run = Utc::now()
-                            + chrono::Duration::minutes(workflow.stability_period_minutes as i64);
+                            + chrono::Duration::minutes(
+                                workflow.stability_period_after(&w.name) as i64
+                            );
+
+                        let environment_name = w.name.clone();
+                        let deployment_id = w.deployment_id;
 
-                        client
+                        let workflow = client
                             .complete_environment(workflow, environments, next_due_to_run)
                             .await
                             .context("completing environment")?;
+                        record_live(&workflow, idx).await;
+                        if let Some(deployment_id) = deployment_id {
+                            github::update_deployment_status(
+                                &workflow.owner,
+                                &workflow.repo,
+                                &deployment_id,
+                                &workflow.deployment_status(
+                                    &environment_name,
+                                    github::DeploymentStatus::Success,
+                                    describe_deployment(
+                                        EnvironmentStatus::Success,
+                                        None,
+                                        soak_minutes,
+                                    ),
+                                ),
+                            )
+                            .await
+                            .context("updating deployment status")?;
+                        }
+                        check_run::sync(&workflow).await;
                         return Ok(());
                     }
                 }
 
+                let runs = github_workflows
+                    .iter()
+                    .map(|r| Run {
+                        id: r.id,
+                        name: r.name.clone(),
+                        url: r.html_url.clone(),
+                    })
+                    .collect::<Vec<_>>();
+                let run_state = run_state(&github_workflows);
                 let status = overall_status(github_workflows);
                 log::info!("step is {:?} for commit sha {}", status, &workflow.sha);
+                let description = describe_deployment(status, run_state, soak_minutes);
+                let environment_name = w.name.clone();
 
                 let mut environments = workflow.environments.clone();
                 if let Some(environment) = environments.get_mut(idx) {
                     environment.status = status;
+                    environment.runs = runs;
 
                     let next_due_to_run = if status.is_terminal() {
                         environment.finished_at = Some(Utc::now());
                         let next_due_to_run = Utc::now()
-                            + chrono::Duration::minutes(workflow.stability_period_minutes as i64);
+                            + chrono::Duration::minutes(
+                                workflow.stability_period_after(&w.name) as i64
+                            );
                         log::info!(
                             "environment {} finished, marking workflow as done, and next due at {:?}",
                             w.name,
@@ -131,10 +246,14 @@ async fn process_workflow(
 
                     let deployment_id = environment.deployment_id.unwrap();
                     let workflow = match status {
-                        EnvironmentStatus::Success => client
-                            .complete_environment(workflow, environments, next_due_to_run)
-                            .await
-                            .context("completing environment")?,
+                        EnvironmentStatus::Success => {
+                            let workflow = client
+                                .complete_environment(workflow, environments, next_due_to_run)
+                                .await
+                                .context("completing environment")?;
+                            record_live(&workflow, idx).await;
+                            workflow
+                        }
                         EnvironmentStatus::Failure => client
                             .fail_environment(workflow, environments, next_due_to_run)
                             .await
@@ -150,30 +269,90 @@ async fn process_workflow(
                         &workflow.owner,
                         &workflow.repo,
                         &deployment_id,
-                        status.into(),
+                        &workflow.deployment_status(&environment_name, status.into(), description),
                     )
                     .await
                     .context("updating deployment status")?;
+                    check_run::sync(&workflow).await;
                 }
 
                 return Ok(());
             };
 
             log::info!("picked up environment {} to process", w.name);
+            let environment_name = w.name.clone();
+
+            let required_contexts = settings::client()
+                .await
+                .get(&workflow.owner, &workflow.repo)
+                .await
+                .context("getting repo settings")?
+                .required_contexts;
+            // Only the first environment waits on checks, the rest follow it.
+            let workflow = if idx == 0 && !required_contexts.is_empty() {
+                match checks::gate(client, workflow, &required_contexts)
+                    .await
+                    .context("checking required contexts")?
+                {
+                    Some(workflow) => workflow,
+                    None => return Ok(()),
+                }
+            } else {
+                workflow
+            };
 
-            let deployment = github::create_deployment(github::CreateDeploymentRequest {
+            // GitHub checks the contexts again as it creates the first deployment. Later
+            // environments follow the first, whatever happens to the checks meanwhile.
+            let deployment = match github::create_deployment(github::CreateDeploymentRequest {
                 owner: &workflow.owner,
                 repo: &workflow.repo,
-                environment: &w.name,
+                environment: &environment_name,
                 git_ref: &workflow.sha,
-                description: "created by pipedream",
+                description: &workflow.deployment_description(),
+                required_contexts: if idx == 0 { &required_contexts } else { &[] },
             })
             .await
-            .context("running github workflow")?;
+            {
+                Ok(deployment) => deployment,
+                Err(e) if e.is::<github::RequiredContextsFailed>() => {
+                    log::info!(
+                        "not deploying commit sha {} to {}: {:#}",
+                        &workflow.sha,
+                        environment_name,
+                        e
+                    );
+                    let mut environments = workflow.environments.clone();
+                    if let Some(environment) = environments.get_mut(idx) {
+                        environment.status = EnvironmentStatus::Failure;
+                        environment.finished_at = Some(Utc::now());
+                    }
+                    let due_to_run = workflow.due_to_run;
+                    let workflow = client
+                        .fail_environment(workflow, environments, due_to_run)
+                        .await
+                        .context("failing environment")?;
+                    check_run::sync(&workflow).await;
+                    return Ok(());
+                }
+                Err(e) => return Err(e).context("running github workflow"),
+            };
 
-            log::info!("environment {} started", w.name);
+            // Link the deployment back to the workflow before any runs pick it up.
+            github::update_deployment_status(
+                &workflow.owner,
+                &workflow.repo,
+                &deployment.id,
+                &workflow.deployment_status(
+                    &environment_name,
+                    github::DeploymentStatus::Pending,
+                    "waiting for GitHub Actions",
+                ),
+            )
+            .await
+            .context("updating deployment status")?;
+
+            log::info!("environment {} started", environment_name);
 
-            let environment_name = w.name.clone();
             let mut environments = workflow.environments.clone();
             if let Some(environment) = environments.get_mut(idx) {
                 environment.status = EnvironmentStatus::Running;
@@ -181,7 +360,7 @@ async fn process_workflow(
                 environment.deployment_id = Some(deployment.id);
             }
 
-            client
+            let workflow = client
                 .update_environments(workflow, environments)
                 .await
                 .context("updating step status")?;
@@ -189,6 +368,7 @@ async fn process_workflow(
                 "environment {} status updated in database",
                 environment_name
             );
+            check_run::sync(&workflow).await;
 
             // Then register a webhook to call back to for updating the status
             // and setting the time of the next environment? Or just poll forever.
@@ -196,3 +376,76 @@ async fn process_workflow(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(status: github::WorkflowStatus) -> github::Workflow {
+        github::Workflow {
+            id: 1,
+            name: "deploy".to_string(),
+            html_url: "https://github.com/owner/repo/actions/runs/1".to_string(),
+            status,
+        }
+    }
+
+    #[test]
+    fn test_cancelled_meanwhile() {
+        let cancelled: Result<(), _> = Err(Cancelled);
+        let cancelled = cancelled
+            .context("completing environment")
+            .context("completing environment")
+            .unwrap_err();
+        assert!(cancelled_meanwhile(&cancelled));
+
+        // A missing item or another refused update is a real failure.
+        let missing: Result<(), _> = Err(crate::aws::DynamodbError::NotFound());
+        let missing = missing.context("completing environment").unwrap_err();
+        assert!(!cancelled_meanwhile(&missing));
+
+        assert!(!cancelled_meanwhile(&anyhow::anyhow!(
+            "listing github workflows"
+        )));
+    }
+
+    #[test]
+    fn test_describe_deployment() {
+        let waiting = run_state(&[
+            run(github::WorkflowStatus::Completed),
+            run(github::WorkflowStatus::Waiting),
+        ]);
+        assert_eq!(waiting, Some("awaiting approval"));
+        assert_eq!(
+            describe_deployment(EnvironmentStatus::Queued, waiting, Some(30)),
+            "awaiting approval"
+        );
+
+        let timed_out = run_state(&[run(github::WorkflowStatus::TimedOut)]);
+        assert_eq!(
+            describe_deployment(EnvironmentStatus::Failure, timed_out, Some(30)),
+            "timed out"
+        );
+        assert_eq!(
+            describe_deployment(EnvironmentStatus::Running, None, Some(30)),
+            "deploying"
+        );
+
+        assert_eq!(
+            describe_deployment(EnvironmentStatus::Success, None, Some(25)),
+            "deployed, soaking for 25m"
+        );
+        assert_eq!(
+            describe_deployment(EnvironmentStatus::Success, None, Some(90)),
+            "deployed, soaking for 1h 30m"
+        );
+        assert_eq!(
+            describe_deployment(EnvironmentStatus::Success, None, Some(120)),
+            "deployed, soaking for 2h"
+        );
+        assert_eq!(
+            describe_deployment(EnvironmentStatus::Success, None, None),
+            "deployed"
+        );
+    }
+}